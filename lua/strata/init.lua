@@ -4,6 +4,7 @@ local module = {
 	actions = require("actions"),
 	window = require("window"),
 	workspace = require("workspace"),
+	output = require("output"),
 	rules = require("rules"),
 	api = api, -- mlua module
 
@@ -11,6 +12,14 @@ local module = {
 	set_config = api.set_config,
 	get_config = api.get_config,
 	update_config = api.update_config,
+	set_gaps = api.set_gaps,
+	get_gaps = api.get_gaps,
+	set_layout = api.set_layout,
+	get_layout = api.get_layout,
+	register_layout = api.register_layout,
+	set_master = api.set_master,
+	get_master = api.get_master,
+	reload = api.reload,
 }
 
 return module
@@ -0,0 +1,12 @@
+local api = require("strata.api")
+
+local module = {}
+
+--- Focuses an output, making the workspace it shows the current one
+---@param output string an output name, or "left" or "right" of the current output
+---@return function
+function module.focus(output)
+	return function() api.focus_output(output) end
+end
+
+return module
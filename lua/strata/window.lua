@@ -20,4 +20,118 @@ function module.close()
 	return function() api.close_window() end
 end
 
+--- Toggles between floating and tiling for the focused window
+---@return function
+function module.toggle_floating()
+	return function() api.toggle_floating() end
+end
+
+--- Toggles fullscreen for the focused window
+---@return function
+function module.toggle_fullscreen()
+	return function() api.toggle_fullscreen() end
+end
+
+--- Toggles between maximized and its place in the layout for the focused window
+---@return function
+function module.toggle_maximized()
+	return function() api.toggle_maximized() end
+end
+
+--- Focuses the closest window in a direction
+---@param direction "left"|"right"|"up"|"down"
+---@return function
+function module.focus(direction)
+	return function() api.focus_direction(direction) end
+end
+
+--- Focuses the next window of the workspace
+---@return function
+function module.focus_next()
+	return function() api.focus_next() end
+end
+
+--- Focuses the previous window of the workspace
+---@return function
+function module.focus_prev()
+	return function() api.focus_prev() end
+end
+
+--- Swaps the focused window with its neighbour in a direction
+---@param direction "left"|"right"|"up"|"down"
+---@return function
+function module.swap(direction)
+	return function() api.swap_direction(direction) end
+end
+
+--- Moves the focused window to the place of its neighbour in a direction, shifting the
+--- windows in between
+---@param direction "left"|"right"|"up"|"down"
+---@return function
+function module.move_in_direction(direction)
+	return function() api.move_direction(direction) end
+end
+
+--- Makes the focused window the master window, the first one of the layout
+---@return function
+function module.promote()
+	return function() api.promote_window() end
+end
+
+--- Grows the focused window on one side by moving the split next to it
+---@param direction "left"|"right"|"up"|"down"
+---@param amount number? in pixels, 40 by default
+---@return function
+function module.grow(direction, amount)
+	return function() api.grow_window(direction, amount) end
+end
+
+--- Shrinks the focused window on one side by moving the split next to it
+---@param direction "left"|"right"|"up"|"down"
+---@param amount number? in pixels, 40 by default
+---@return function
+function module.shrink(direction, amount)
+	return function() api.shrink_window(direction, amount) end
+end
+
+--- Flips the split holding the focused window between side by side and stacked
+---@return function
+function module.toggle_split()
+	return function() api.toggle_split() end
+end
+
+--- Moves the focused window to the workspace shown on another output
+---@param output string an output name, or "left" or "right" of the current output
+---@return function
+function module.move_to_output(output)
+	return function() api.move_window_to_output(output) end
+end
+
+--- Gives the column of the focused window the next of the preset widths
+---@return function
+function module.cycle_width()
+	return function() api.cycle_width() end
+end
+
+--- Makes the next window open on one side of the focused window. Preselecting the same side
+--- again cancels it.
+---@param direction "left"|"right"|"up"|"down"
+---@param ratio number? the share of the focused window's area the new window gets, 0.5 by default
+---@return function
+function module.preselect(direction, ratio)
+	return function() api.preselect(direction, ratio) end
+end
+
+--- Cancels the preselection on the current workspace
+---@return function
+function module.cancel_preselection()
+	return function() api.cancel_preselection() end
+end
+
+--- Centers the focused window, if it is floating
+---@return function
+function module.center()
+	return function() api.center_window() end
+end
+
 return module
\ No newline at end of file
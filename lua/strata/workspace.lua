@@ -9,4 +9,41 @@ function module.switch(id)
 	return function() api.switch_to_ws(id) end
 end
 
+--- Changes the layout of a workspace
+---@param layout "dwindle"|"master_stack"|"monocle"|"scrolling"|"grid"|"spiral"|string
+---@param id number? the current workspace by default
+---@return function
+function module.set_layout(layout, id)
+	return function() api.set_layout(layout, id) end
+end
+
+--- Adds to the number of master windows of the current workspace
+---@param delta number
+---@return function
+function module.inc_master_count(delta)
+	return function() api.inc_master_count(delta) end
+end
+
+--- Adds to the share of the output master windows take on the current workspace
+---@param delta number
+---@return function
+function module.inc_master_ratio(delta)
+	return function() api.inc_master_ratio(delta) end
+end
+
+--- Moves a workspace to another output and shows it there
+---@param output string an output name, or "left" or "right" of the current output
+---@param id number? the current workspace by default
+---@return function
+function module.move_to_output(output, id)
+	return function() api.move_workspace_to_output(output, id) end
+end
+
+--- Gives every split of a workspace back an even ratio
+---@param id number? the current workspace by default
+---@return function
+function module.reset_ratios(id)
+	return function() api.reset_ratios(id) end
+end
+
 return module
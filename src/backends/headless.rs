@@ -0,0 +1,214 @@
+// Copyright 2023 the Strata authors
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::time::Duration;
+
+use log::{
+	error,
+	info,
+};
+use smithay::{
+	backend::{
+		allocator::Fourcc,
+		egl::{
+			EGLContext,
+			EGLDevice,
+			EGLDisplay,
+		},
+		renderer::{
+			damage::OutputDamageTracker,
+			gles::GlesRenderbuffer,
+			glow::GlowRenderer,
+			Bind,
+			Offscreen,
+		},
+	},
+	output::{
+		Mode,
+		Output,
+		PhysicalProperties,
+		Subpixel,
+	},
+	reexports::calloop::{
+		timer::{
+			TimeoutAction,
+			Timer,
+		},
+		EventLoop,
+	},
+	utils::{
+		Physical,
+		Size,
+		Transform,
+	},
+};
+
+use crate::{
+	backends::Backend,
+	config::ConfigPaths,
+	decorations::BorderShader,
+	state::{
+		self,
+		StrataComp,
+		StrataState,
+	},
+	Args,
+};
+
+pub struct HeadlessData {
+	pub renderer: GlowRenderer,
+	pub outputs: Vec<HeadlessOutput>,
+}
+
+pub struct HeadlessOutput {
+	pub output: Output,
+	pub damage_tracker: OutputDamageTracker,
+	pub buffer: GlesRenderbuffer,
+}
+
+pub fn parse_size(size: &str) -> Option<Size<i32, Physical>> {
+	let (w, h) = size.split_once('x')?;
+	let (w, h) = (w.trim().parse().ok()?, h.trim().parse().ok()?);
+	if w <= 0 || h <= 0 {
+		return None;
+	}
+
+	Some((w, h).into())
+}
+
+/// Rendering goes through EGL, so the headless backend needs an EGL device. Without a GPU,
+/// Mesa's software device (`EGL_MESA_device_software`, llvmpipe) does the job.
+fn init_renderer() -> anyhow::Result<GlowRenderer> {
+	// prefer a software device so this works without any gpu, e.g. in CI
+	let mut devices: Vec<_> = EGLDevice::enumerate()
+		.map_err(|err| anyhow::anyhow!("no EGL devices can be listed: {}", err))?
+		.collect();
+	devices.sort_by_key(|device| {
+		!device.extensions().iter().any(|ext| ext == "EGL_MESA_device_software")
+	});
+	let device = devices.into_iter().next().ok_or_else(|| {
+		anyhow::anyhow!("no EGL device found, the headless backend needs one, e.g. Mesa's llvmpipe")
+	})?;
+
+	let display = unsafe { EGLDisplay::new(device)? };
+	let context = EGLContext::new(&display)?;
+	let renderer = unsafe { GlowRenderer::new(context)? };
+
+	Ok(renderer)
+}
+
+pub fn init_headless(args: &Args) -> anyhow::Result<()> {
+	// checked before anything else is set up, so that the backend fails fast without EGL
+	let mut renderer = init_renderer()?;
+
+	let mut event_loop: EventLoop<StrataState> = EventLoop::try_new().unwrap();
+	let (display, socket) = state::init_wayland_listener(&event_loop);
+
+	let size = parse_size(&args.output_size).unwrap_or_else(|| {
+		error!("Invalid output size {:?}, falling back to 1920x1080", args.output_size);
+		(1920, 1080).into()
+	});
+
+	let mode = Mode { size, refresh: 60_000 };
+	let mut outputs = Vec::new();
+	for i in 0..args.outputs.max(1) {
+		let output = Output::new(
+			format!("HEADLESS-{}", i + 1),
+			PhysicalProperties {
+				size: (0, 0).into(),
+				subpixel: Subpixel::Unknown,
+				make: "Strata".into(),
+				model: "Headless".into(),
+			},
+		);
+		let _global = output.create_global::<StrataComp>(&display.handle());
+		output.change_current_state(Some(mode), Some(Transform::Normal), None, Some((0, 0).into()));
+		output.set_preferred(mode);
+
+		let buffer = Offscreen::<GlesRenderbuffer>::create_buffer(
+			&mut renderer,
+			Fourcc::Abgr8888,
+			Size::from((size.w, size.h)),
+		)?;
+
+		let damage_tracker = OutputDamageTracker::from_output(&output);
+		outputs.push(HeadlessOutput { output, damage_tracker, buffer });
+	}
+	info!("Created {} headless output(s) of size {}x{}", outputs.len(), size.w, size.h);
+
+	BorderShader::init(&mut renderer);
+
+	let mut comp = StrataComp::new(
+		&event_loop,
+		&display,
+		socket,
+		"headless".to_string(),
+		Backend::Headless(HeadlessData { renderer, outputs }),
+	);
+	let outputs: Vec<_> =
+		comp.backend.headless().outputs.iter().map(|o| o.output.clone()).collect();
+	for output in outputs {
+		comp.workspaces.add_output(output);
+	}
+
+	std::env::set_var("WAYLAND_DISPLAY", &comp.socket_name);
+	info!("Listening on {:?}", comp.socket_name);
+
+	let timer = Timer::immediate();
+	event_loop
+		.handle()
+		.insert_source(timer, move |_, _, data| {
+			data.comp.borrow_mut().headless_update();
+			TimeoutAction::ToDuration(Duration::from_millis(16))
+		})
+		.unwrap();
+
+	let mut data = StrataState::new(comp, display, ConfigPaths::find(args));
+	event_loop.run(None, &mut data, |state| state.run_custom_layouts())?;
+	Ok(())
+}
+
+impl StrataComp {
+	pub fn headless_update(&mut self) {
+		let focus = self.focused_window();
+		let Backend::Headless(headless) = &mut self.backend else {
+			return;
+		};
+
+		let mut rendered = Vec::new();
+		for headless_output in headless.outputs.iter_mut() {
+			if let Err(err) = headless.renderer.bind(headless_output.buffer.clone()) {
+				error!("Failed to bind offscreen buffer: {}", err);
+				continue;
+			}
+
+			let render_elements = self
+				.workspaces
+				.on_output(&headless_output.output)
+				.map(|workspace| {
+					workspace.render_elements(
+						&mut headless.renderer,
+						focus.as_ref(),
+						&self.config.options.decorations.border,
+					)
+				})
+				.unwrap_or_default();
+			if let Err(err) = headless_output.damage_tracker.render_output(
+				&mut headless.renderer,
+				0,
+				&render_elements,
+				[0.1, 0.1, 0.1, 1.0],
+			) {
+				error!("Failed to render headless output: {}", err);
+				continue;
+			}
+
+			rendered.push(headless_output.output.clone());
+		}
+		BorderShader::cleanup(&mut headless.renderer);
+
+		for output in rendered {
+			self.post_repaint(&output);
+		}
+	}
+}
@@ -1,19 +1,68 @@
 // Copyright 2023 the Strata authors
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+pub mod headless;
+pub mod udev;
 pub mod winit;
 use log::error;
 
-pub fn init_with_backend(backend_name: &str) {
-	match backend_name {
-		"winit" => {
-			winit::init_winit();
+use self::{
+	headless::HeadlessData,
+	udev::UdevData,
+	winit::WinitData,
+};
+use crate::Args;
+
+pub enum Backend {
+	Winit(WinitData),
+	Udev(UdevData),
+	Headless(HeadlessData),
+}
+
+impl Backend {
+	pub fn winit(&mut self) -> &mut WinitData {
+		match self {
+			Backend::Winit(data) => data,
+			_ => unreachable!("backend is not winit"),
+		}
+	}
+
+	pub fn udev(&mut self) -> &mut UdevData {
+		match self {
+			Backend::Udev(data) => data,
+			_ => unreachable!("backend is not udev"),
+		}
+	}
+
+	pub fn headless(&mut self) -> &mut HeadlessData {
+		match self {
+			Backend::Headless(data) => data,
+			_ => unreachable!("backend is not headless"),
 		}
-		"udev" => {
-			error!("Udev is not implemented yet!");
+	}
+
+	pub fn change_vt(&mut self, vt: i32) {
+		match self {
+			Backend::Winit(_) | Backend::Headless(_) => {}
+			Backend::Udev(data) => data.change_vt(vt),
 		}
-		unknown => {
-			error!("Unknown backend provided: {}", unknown)
+	}
+}
+
+/// Runs the compositor on the backend picked in `args`. Errors are logged and returned, so
+/// that a backend that can't start makes Strata exit with a failure status.
+pub fn init_with_backend(args: &Args) -> anyhow::Result<()> {
+	let res = match args.backend.as_str() {
+		"winit" => {
+			winit::init_winit(args);
+			Ok(())
 		}
+		"udev" => udev::init_udev(args),
+		"headless" => headless::init_headless(args),
+		unknown => Err(anyhow::anyhow!("unknown backend provided: {}", unknown)),
+	};
+	if let Err(err) = &res {
+		error!("Failed to start the {} backend: {:#}", args.backend, err);
 	}
+	res
 }
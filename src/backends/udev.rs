@@ -0,0 +1,587 @@
+// Copyright 2023 the Strata authors
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+	collections::HashMap,
+	path::Path,
+};
+
+use log::{
+	error,
+	info,
+	warn,
+};
+use smithay::{
+	backend::{
+		allocator::{
+			gbm::{
+				GbmAllocator,
+				GbmBufferFlags,
+				GbmDevice,
+			},
+			Fourcc,
+		},
+		drm::{
+			DrmDevice,
+			DrmDeviceFd,
+			DrmEvent,
+			DrmNode,
+			GbmBufferedSurface,
+			NodeType,
+		},
+		egl::{
+			EGLDevice,
+			EGLDisplay,
+		},
+		libinput::{
+			LibinputInputBackend,
+			LibinputSessionInterface,
+		},
+		renderer::{
+			damage::OutputDamageTracker,
+			glow::GlowRenderer,
+			multigpu::{
+				gbm::GbmGlesBackend,
+				GpuManager,
+			},
+			Bind,
+		},
+		session::{
+			libseat::LibSeatSession,
+			Event as SessionEvent,
+			Session,
+		},
+		udev::{
+			all_gpus,
+			primary_gpu,
+			UdevBackend,
+			UdevEvent,
+		},
+	},
+	output::{
+		Mode,
+		Output,
+		PhysicalProperties,
+		Subpixel,
+	},
+	reexports::{
+		calloop::{
+			EventLoop,
+			RegistrationToken,
+		},
+		drm::control::{
+			connector,
+			crtc,
+			ModeTypeFlags,
+		},
+		input::Libinput,
+		rustix::fs::OFlags,
+		wayland_server::backend::GlobalId,
+	},
+	utils::{
+		DeviceFd,
+		Transform,
+	},
+};
+use smithay_drm_extras::{
+	drm_scanner::{
+		DrmScanEvent,
+		DrmScanner,
+	},
+	edid::EdidInfo,
+};
+
+use crate::{
+	backends::Backend,
+	config::ConfigPaths,
+	decorations::BorderShader,
+	state::{
+		self,
+		StrataComp,
+		StrataState,
+	},
+	Args,
+};
+
+const SUPPORTED_FORMATS: &[Fourcc] = &[Fourcc::Abgr8888, Fourcc::Argb8888];
+
+pub struct UdevData {
+	pub session: LibSeatSession,
+	pub primary_gpu: DrmNode,
+	pub gpus: GpuManager<GbmGlesBackend<GlowRenderer>>,
+	pub devices: HashMap<DrmNode, Device>,
+	/// Whether a render of the outputs is already scheduled for when the event loop is idle.
+	pub render_scheduled: bool,
+}
+
+pub struct Device {
+	pub drm: DrmDevice,
+	pub gbm: GbmDevice<DrmDeviceFd>,
+	pub render_node: DrmNode,
+	pub drm_scanner: DrmScanner,
+	pub surfaces: HashMap<crtc::Handle, Surface>,
+	pub registration_token: RegistrationToken,
+}
+
+pub struct Surface {
+	pub output: Output,
+	pub global: GlobalId,
+	pub gbm_surface: GbmBufferedSurface<GbmAllocator<DrmDeviceFd>, ()>,
+	pub damage_tracker: OutputDamageTracker,
+	/// A frame is queued, its vblank renders the next one.
+	pub queued: bool,
+}
+
+impl UdevData {
+	pub fn change_vt(&mut self, vt: i32) {
+		if let Err(err) = self.session.change_vt(vt) {
+			error!("Failed to switch to vt {}: {}", vt, err);
+		}
+	}
+}
+
+pub fn init_udev(args: &Args) -> anyhow::Result<()> {
+	let mut event_loop: EventLoop<StrataState> = EventLoop::try_new()?;
+	let (display, socket) = state::init_wayland_listener(&event_loop);
+
+	let (session, notifier) = LibSeatSession::new()
+		.map_err(|err| anyhow::anyhow!("could not initialize a session: {}", err))?;
+	let seat_name = session.seat();
+
+	let primary_gpu = match primary_gpu(&seat_name)?
+		.and_then(|path| DrmNode::from_path(path).ok()?.node_with_type(NodeType::Render)?.ok())
+	{
+		Some(node) => node,
+		None => all_gpus(&seat_name)?
+			.into_iter()
+			.find_map(|path| DrmNode::from_path(path).ok())
+			.ok_or_else(|| anyhow::anyhow!("no GPU found"))?,
+	};
+	info!("Using {} as primary gpu.", primary_gpu);
+
+	let gpus = GpuManager::new(GbmGlesBackend::default())?;
+	let data =
+		UdevData { session, primary_gpu, gpus, devices: HashMap::new(), render_scheduled: false };
+
+	let mut comp =
+		StrataComp::new(&event_loop, &display, socket, seat_name.clone(), Backend::Udev(data));
+
+	std::env::set_var("WAYLAND_DISPLAY", &comp.socket_name);
+
+	let udev_backend = UdevBackend::new(&seat_name)?;
+	for (device_id, path) in udev_backend.device_list() {
+		match DrmNode::from_dev_id(device_id) {
+			Ok(node) => {
+				if let Err(err) = comp.device_added(node, path) {
+					error!("Skipping device {}: {}", node, err);
+				}
+			}
+			Err(err) => error!("Invalid drm device {}: {}", path.display(), err),
+		}
+	}
+
+	event_loop
+		.handle()
+		.insert_source(udev_backend, move |event, _, data| {
+			let mut comp = data.comp.borrow_mut();
+			match event {
+				UdevEvent::Added { device_id, path } => {
+					if let Ok(node) = DrmNode::from_dev_id(device_id) {
+						if let Err(err) = comp.device_added(node, &path) {
+							error!("Skipping device {}: {}", node, err);
+						}
+					}
+				}
+				UdevEvent::Changed { device_id } => {
+					if let Ok(node) = DrmNode::from_dev_id(device_id) {
+						comp.device_changed(node);
+					}
+				}
+				UdevEvent::Removed { device_id } => {
+					if let Ok(node) = DrmNode::from_dev_id(device_id) {
+						comp.device_removed(node);
+					}
+				}
+			}
+		})
+		.map_err(|e| anyhow::anyhow!("could not watch udev devices: {}", e.error))?;
+
+	let mut libinput_context = Libinput::new_with_udev::<LibinputSessionInterface<LibSeatSession>>(
+		comp.backend.udev().session.clone().into(),
+	);
+	libinput_context
+		.udev_assign_seat(&seat_name)
+		.map_err(|()| anyhow::anyhow!("could not assign seat {} to libinput", seat_name))?;
+	let libinput_backend = LibinputInputBackend::new(libinput_context.clone());
+
+	event_loop
+		.handle()
+		.insert_source(libinput_backend, move |event, _, data| {
+			if let Err(err) = data.process_input_event(event) {
+				error!("Failed to process input event: {:#?}", err);
+			}
+		})
+		.map_err(|e| anyhow::anyhow!("could not listen to libinput: {}", e.error))?;
+
+	event_loop
+		.handle()
+		.insert_source(notifier, move |event, _, data| match event {
+			SessionEvent::PauseSession => {
+				info!("Pausing session");
+				libinput_context.suspend();
+				for device in data.comp.borrow_mut().backend.udev().devices.values_mut() {
+					device.drm.pause();
+				}
+			}
+			SessionEvent::ActivateSession => {
+				info!("Resuming session");
+				if let Err(err) = libinput_context.resume() {
+					error!("Failed to resume libinput context: {:?}", err);
+				}
+
+				let mut comp = data.comp.borrow_mut();
+				let mut crtcs = Vec::new();
+				for (node, device) in comp.backend.udev().devices.iter_mut() {
+					if let Err(err) = device.drm.activate(false) {
+						error!("Failed to activate drm device {}: {}", node, err);
+					}
+					for (crtc, surface) in device.surfaces.iter_mut() {
+						surface.gbm_surface.reset_buffers();
+						surface.queued = false;
+						crtcs.push((*node, *crtc));
+					}
+				}
+				for (node, crtc) in crtcs {
+					comp.udev_render(node, crtc);
+				}
+			}
+		})
+		.map_err(|e| anyhow::anyhow!("could not listen to the session: {}", e.error))?;
+
+	let mut data = StrataState::new(comp, display, ConfigPaths::find(args));
+	event_loop.run(None, &mut data, |state| state.run_custom_layouts())?;
+	Ok(())
+}
+
+impl StrataComp {
+	pub fn device_added(&mut self, node: DrmNode, path: &Path) -> anyhow::Result<()> {
+		let udev = self.backend.udev();
+		let fd = udev
+			.session
+			.open(path, OFlags::RDWR | OFlags::CLOEXEC | OFlags::NOCTTY | OFlags::NONBLOCK)?;
+		let fd = DrmDeviceFd::new(DeviceFd::from(fd));
+
+		let (drm, notifier) = DrmDevice::new(fd.clone(), true)?;
+		let gbm = GbmDevice::new(fd)?;
+
+		let registration_token = self
+			.loop_handle
+			.insert_source(notifier, move |event, _, data| match event {
+				DrmEvent::VBlank(crtc) => data.comp.borrow_mut().udev_frame_finish(node, crtc),
+				DrmEvent::Error(err) => error!("DRM error on {}: {:?}", node, err),
+			})
+			.map_err(|err| err.error)?;
+
+		let render_node = EGLDevice::device_for_display(&unsafe { EGLDisplay::new(gbm.clone())? })
+			.ok()
+			.and_then(|device| device.try_get_render_node().ok().flatten())
+			.unwrap_or(node);
+
+		udev.gpus.as_mut().add_node(render_node, gbm.clone())?;
+		BorderShader::init(udev.gpus.single_renderer(&render_node)?.as_mut());
+
+		udev.devices.insert(
+			node,
+			Device {
+				drm,
+				gbm,
+				render_node,
+				drm_scanner: DrmScanner::new(),
+				surfaces: HashMap::new(),
+				registration_token,
+			},
+		);
+
+		self.device_changed(node);
+
+		Ok(())
+	}
+
+	pub fn device_changed(&mut self, node: DrmNode) {
+		let Some(device) = self.backend.udev().devices.get_mut(&node) else {
+			return;
+		};
+
+		for event in device.drm_scanner.scan_connectors(&device.drm) {
+			match event {
+				DrmScanEvent::Connected { connector, crtc: Some(crtc) } => {
+					self.connector_connected(node, connector, crtc);
+				}
+				DrmScanEvent::Disconnected { connector, crtc: Some(crtc) } => {
+					self.connector_disconnected(node, connector, crtc);
+				}
+				_ => {}
+			}
+		}
+	}
+
+	pub fn device_removed(&mut self, node: DrmNode) {
+		let crtcs: Vec<_> = match self.backend.udev().devices.get(&node) {
+			Some(device) => {
+				device.drm_scanner.crtcs().map(|(info, crtc)| (info.clone(), crtc)).collect()
+			}
+			None => return,
+		};
+
+		for (connector, crtc) in crtcs {
+			self.connector_disconnected(node, connector, crtc);
+		}
+
+		let udev = self.backend.udev();
+		if let Some(device) = udev.devices.remove(&node) {
+			udev.gpus.as_mut().remove_node(&device.render_node);
+			self.loop_handle.remove(device.registration_token);
+		}
+	}
+
+	fn connector_connected(
+		&mut self,
+		node: DrmNode,
+		connector: connector::Info,
+		crtc: crtc::Handle,
+	) {
+		let udev = self.backend.udev();
+		let device = udev.devices.get_mut(&node).unwrap();
+
+		let output_name =
+			format!("{}-{}", connector.interface().as_str(), connector.interface_id());
+		info!("Connector {} connected", output_name);
+
+		let Some(drm_mode) = connector
+			.modes()
+			.iter()
+			.find(|mode| mode.mode_type().contains(ModeTypeFlags::PREFERRED))
+			.or_else(|| connector.modes().first())
+			.copied()
+		else {
+			warn!("Connector {} has no modes", output_name);
+			return;
+		};
+
+		let drm_surface = match device.drm.create_surface(crtc, drm_mode, &[connector.handle()]) {
+			Ok(surface) => surface,
+			Err(err) => {
+				error!("Failed to create drm surface for {}: {}", output_name, err);
+				return;
+			}
+		};
+
+		let render_formats = match udev.gpus.single_renderer(&device.render_node) {
+			Ok(mut renderer) => renderer.as_mut().egl_context().dmabuf_render_formats().clone(),
+			Err(err) => {
+				error!("Failed to get renderer for {}: {}", device.render_node, err);
+				return;
+			}
+		};
+
+		let allocator = GbmAllocator::new(
+			device.gbm.clone(),
+			GbmBufferFlags::RENDERING | GbmBufferFlags::SCANOUT,
+		);
+		let gbm_surface = match GbmBufferedSurface::new(
+			drm_surface,
+			allocator,
+			SUPPORTED_FORMATS,
+			render_formats,
+		) {
+			Ok(surface) => surface,
+			Err(err) => {
+				error!("Failed to create gbm surface for {}: {}", output_name, err);
+				return;
+			}
+		};
+
+		let (make, model) = EdidInfo::for_connector(&device.drm, connector.handle())
+			.map(|info| (info.manufacturer, info.model))
+			.unwrap_or_else(|| ("Unknown".into(), "Unknown".into()));
+		let (phys_w, phys_h) = connector.size().unwrap_or((0, 0));
+
+		let output = Output::new(
+			output_name,
+			PhysicalProperties {
+				size: (phys_w as i32, phys_h as i32).into(),
+				subpixel: Subpixel::Unknown,
+				make,
+				model,
+			},
+		);
+		let global = output.create_global::<StrataComp>(&self.dh);
+		let mode = Mode::from(drm_mode);
+		output.change_current_state(Some(mode), Some(Transform::Normal), None, Some((0, 0).into()));
+		output.set_preferred(mode);
+
+		let damage_tracker = OutputDamageTracker::from_output(&output);
+
+		device.surfaces.insert(
+			crtc,
+			Surface { output: output.clone(), global, gbm_surface, damage_tracker, queued: false },
+		);
+
+		self.add_output(&output);
+
+		self.udev_render(node, crtc);
+	}
+
+	fn connector_disconnected(
+		&mut self,
+		node: DrmNode,
+		connector: connector::Info,
+		crtc: crtc::Handle,
+	) {
+		let Some(device) = self.backend.udev().devices.get_mut(&node) else {
+			return;
+		};
+		let Some(surface) = device.surfaces.remove(&crtc) else {
+			return;
+		};
+
+		info!(
+			"Connector {}-{} disconnected",
+			connector.interface().as_str(),
+			connector.interface_id()
+		);
+
+		self.remove_output(&surface.output);
+		self.dh.remove_global::<StrataComp>(surface.global);
+	}
+
+	fn udev_frame_finish(&mut self, node: DrmNode, crtc: crtc::Handle) {
+		let Some(surface) = self
+			.backend
+			.udev()
+			.devices
+			.get_mut(&node)
+			.and_then(|device| device.surfaces.get_mut(&crtc))
+		else {
+			return;
+		};
+
+		surface.queued = false;
+		if let Err(err) = surface.gbm_surface.frame_submitted() {
+			warn!("Error during frame submission: {}", err);
+		}
+
+		self.udev_render(node, crtc);
+	}
+
+	/// Renders the outputs once the event loop is idle, for when something on them changed.
+	/// Outputs waiting for a vblank are rendered when it comes in instead.
+	pub fn schedule_render(&mut self) {
+		let Backend::Udev(udev) = &mut self.backend else {
+			return;
+		};
+		if udev.render_scheduled {
+			return;
+		}
+		udev.render_scheduled = true;
+
+		self.loop_handle.insert_idle(|data| {
+			let mut comp = data.comp.borrow_mut();
+			let udev = comp.backend.udev();
+			udev.render_scheduled = false;
+			let crtcs: Vec<_> = udev
+				.devices
+				.iter()
+				.flat_map(|(node, device)| {
+					device
+						.surfaces
+						.iter()
+						.filter(|(_, surface)| !surface.queued)
+						.map(|(crtc, _)| (*node, *crtc))
+				})
+				.collect();
+			for (node, crtc) in crtcs {
+				comp.udev_render(node, crtc);
+			}
+		});
+	}
+
+	pub fn udev_render(&mut self, node: DrmNode, crtc: crtc::Handle) {
+		if !self.backend.udev().session.is_active() {
+			return;
+		}
+
+		match self.udev_render_surface(node, crtc) {
+			Ok(Some(output)) => {
+				if let Some(surface) = self
+					.backend
+					.udev()
+					.devices
+					.get_mut(&node)
+					.and_then(|device| device.surfaces.get_mut(&crtc))
+				{
+					surface.queued = true;
+				}
+				self.post_repaint(&output);
+			}
+			// without damage nothing is queued, the next render waits for `schedule_render`
+			Ok(None) => {}
+			Err(err) => error!("Failed to render on {}: {}", node, err),
+		}
+	}
+
+	fn udev_render_surface(
+		&mut self,
+		node: DrmNode,
+		crtc: crtc::Handle,
+	) -> anyhow::Result<Option<Output>> {
+		let focus = self.focused_window();
+		let Backend::Udev(udev) = &mut self.backend else {
+			return Ok(None);
+		};
+		let Some(device) = udev.devices.get_mut(&node) else {
+			return Ok(None);
+		};
+		let Some(surface) = device.surfaces.get_mut(&crtc) else {
+			return Ok(None);
+		};
+		let mut renderer = if udev.primary_gpu == device.render_node {
+			udev.gpus.single_renderer(&device.render_node)?
+		} else {
+			udev.gpus.renderer(
+				&udev.primary_gpu,
+				&device.render_node,
+				surface.gbm_surface.format(),
+			)?
+		};
+
+		let render_elements = self
+			.workspaces
+			.on_output(&surface.output)
+			.map(|workspace| {
+				workspace.render_elements(
+					&mut renderer,
+					focus.as_ref(),
+					&self.config.options.decorations.border,
+				)
+			})
+			.unwrap_or_default();
+
+		let (dmabuf, age) = surface.gbm_surface.next_buffer()?;
+		renderer.bind(dmabuf)?;
+		let res = surface.damage_tracker.render_output(
+			&mut renderer,
+			age as usize,
+			&render_elements,
+			[0.1, 0.1, 0.1, 1.0],
+		)?;
+		BorderShader::cleanup(renderer.as_mut());
+
+		if res.damage.is_none() {
+			return Ok(None);
+		}
+		surface.gbm_surface.queue_buffer(Some(res.sync), res.damage, ())?;
+
+		Ok(Some(surface.output.clone()))
+	}
+}
@@ -2,24 +2,27 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use crate::{
-	bindings,
+	backends::Backend,
+	config::ConfigPaths,
 	decorations::BorderShader,
 	state::{
 		self,
 		StrataComp,
 		StrataState,
 	},
-};
-use piccolo::{
-	self as lua,
+	Args,
 };
 use smithay::{
 	backend::{
-		renderer::damage::OutputDamageTracker,
+		renderer::{
+			damage::OutputDamageTracker,
+			glow::GlowRenderer,
+		},
 		winit::{
 			self,
 			WinitEvent,
 			WinitEventLoop,
+			WinitGraphicsBackend,
 		},
 	},
 	output::{
@@ -38,15 +41,21 @@ use smithay::{
 		},
 		winit::platform::pump_events::PumpStatus,
 	},
-	utils::Transform,
-};
-use std::{
-	cell::RefCell,
-	rc::Rc,
-	time::Duration,
+	utils::{
+		Physical,
+		Rectangle,
+		Transform,
+	},
 };
+use std::time::Duration;
 
-pub fn init_winit() {
+pub struct WinitData {
+	pub backend: WinitGraphicsBackend<GlowRenderer>,
+	pub damage_tracker: OutputDamageTracker,
+	pub output: Output,
+}
+
+pub fn init_winit(args: &Args) {
 	let mut event_loop: EventLoop<StrataState> = EventLoop::try_new().unwrap();
 	let (display, socket) = state::init_wayland_listener(&event_loop);
 	let display_handle = display.handle();
@@ -64,19 +73,16 @@ pub fn init_winit() {
 	let _global = output.create_global::<StrataComp>(&display_handle);
 	output.change_current_state(Some(mode), Some(Transform::Flipped180), None, Some((0, 0).into()));
 	output.set_preferred(mode);
-	let damage_tracked_renderer = OutputDamageTracker::from_output(&output);
+	let damage_tracker = OutputDamageTracker::from_output(&output);
 	let mut comp = StrataComp::new(
 		&event_loop,
 		&display,
 		socket,
 		"winit".to_string(),
-		backend,
-		damage_tracked_renderer,
+		Backend::Winit(WinitData { backend, damage_tracker, output: output.clone() }),
 	);
-	BorderShader::init(comp.backend.renderer());
-	for workspace in comp.workspaces.iter() {
-		workspace.add_output(output.clone());
-	}
+	BorderShader::init(comp.backend.winit().backend.renderer());
+	comp.workspaces.add_output(output.clone());
 
 	std::env::set_var("WAYLAND_DISPLAY", &comp.socket_name);
 
@@ -89,60 +95,23 @@ pub fn init_winit() {
 		})
 		.unwrap();
 
-	let mut lua_vm = lua::Lua::full();
-	let comp = Rc::new(RefCell::new(comp));
-
-	let ex = lua_vm
-		.try_enter(|ctx| {
-			bindings::register(ctx, Rc::clone(&comp))?;
-
-			let main = lua::Closure::load(
-				ctx,
-				None,
-				r#"
-				local Key = strata.input.Key
-				local Mod = strata.input.Mod
-
-				-- print(Mod.Super_L)
-				-- print(Key.Escape)
-
-				local _ = Key({ Mod.Control_L, Mod.Alt_L }, Key.Return, function()
-					strata.spawn('kitty')
-				end)
-
-				local _ = Key({ Mod.Control_L, Mod.Alt_L }, Key.Escape, function()
-					strata:quit()
-				end)
-				"#
-				.as_bytes(),
-			)?;
-
-			Ok(ctx.stash(lua::Executor::start(ctx, main.into(), ())))
-		})
-		.unwrap();
-
-	if let Err(e) = lua_vm.execute::<()>(&ex) {
-		println!("{:#?}", e);
-	}
-
-	let mut data = StrataState { lua: lua_vm, comp, display };
-	event_loop.run(None, &mut data, move |_| {}).unwrap();
+	let mut data = StrataState::new(comp, display, ConfigPaths::find(args));
+	event_loop.run(None, &mut data, |state| state.run_custom_layouts()).unwrap();
 }
 
 pub fn winit_dispatch(winit: &mut WinitEventLoop, state: &mut StrataState, output: &Output) {
 	// process winit events
-	let res = winit.dispatch_new_events(|event| {
-		match event {
-			WinitEvent::Resized { size, .. } => {
-				output.change_current_state(Some(Mode { size, refresh: 60_000 }), None, None, None);
-			}
-			WinitEvent::Input(event) => {
-				if let Err(e) = state.process_input_event(event) {
-					panic!("{:#?}", e);
-				}
+	let res = winit.dispatch_new_events(|event| match event {
+		WinitEvent::Resized { size, .. } => {
+			output.change_current_state(Some(Mode { size, refresh: 60_000 }), None, None, None);
+			state.comp.borrow_mut().workspaces.arrange_outputs();
+		}
+		WinitEvent::Input(event) => {
+			if let Err(e) = state.process_input_event(event) {
+				panic!("{:#?}", e);
 			}
-			_ => (),
 		}
+		_ => (),
 	});
 
 	if let PumpStatus::Exit(_) = res {
@@ -151,3 +120,43 @@ pub fn winit_dispatch(winit: &mut WinitEventLoop, state: &mut StrataState, outpu
 		state.comp.borrow_mut().winit_update();
 	}
 }
+
+impl StrataComp {
+	fn winit_render(&mut self) -> Option<Vec<Rectangle<i32, Physical>>> {
+		let focus = self.focused_window();
+		let winit = self.backend.winit();
+		let age = winit.backend.buffer_age().unwrap_or(0);
+		winit.backend.bind().unwrap();
+		let render_elements = self
+			.workspaces
+			.on_output(&winit.output)
+			.map(|workspace| {
+				workspace.render_elements(
+					winit.backend.renderer(),
+					focus.as_ref(),
+					&self.config.options.decorations.border,
+				)
+			})
+			.unwrap_or_default();
+		winit
+			.damage_tracker
+			.render_output(winit.backend.renderer(), age, &render_elements, [0.1, 0.1, 0.1, 1.0])
+			.unwrap()
+			.damage
+	}
+
+	pub fn winit_update(&mut self) {
+		let damage = self.winit_render();
+
+		// damage tracking
+		let winit = self.backend.winit();
+		if let Some(damage) = damage {
+			winit.backend.submit(Some(&damage)).unwrap();
+		}
+		BorderShader::cleanup(winit.backend.renderer());
+
+		// sync and cleanups
+		let output = winit.output.clone();
+		self.post_repaint(&output);
+	}
+}
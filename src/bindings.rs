@@ -5,14 +5,64 @@ use std::{
 	cell::RefCell,
 	process::Command,
 	rc::Rc,
+	str::FromStr,
 };
 
+use log::error;
 use piccolo as lua;
+use smithay::output::Output;
+use strata_core::{
+	FromLua,
+	ToLua,
+	UpdateFromLua,
+};
 
-use crate::state::StrataComp;
+use crate::{
+	config::{
+		Config,
+		LayoutKind,
+	},
+	state::StrataComp,
+	tiling::refresh_geometry,
+	workspaces::{
+		Direction,
+		Gaps,
+	},
+};
 
 pub mod input;
 
+/// Workspaces are numbered from 1 on the Lua side.
+fn workspace_index(comp: &StrataComp, id: u8) -> anyhow::Result<u8> {
+	match id.checked_sub(1) {
+		Some(index) if (index as usize) < comp.workspaces.len() => Ok(index),
+		_ => anyhow::bail!("no workspace {}", id),
+	}
+}
+
+/// Resolves an optional workspace argument, defaulting to the current workspace.
+fn workspace_arg(comp: &StrataComp, id: Option<u8>) -> anyhow::Result<u8> {
+	match id {
+		Some(id) => workspace_index(comp, id),
+		None => Ok(comp.workspaces.current),
+	}
+}
+
+/// How many pixels `grow_window`/`shrink_window` resize by when no amount is given.
+const RESIZE_STEP: i32 = 40;
+
+/// Resolves an output argument: an output name, or `"left"` or `"right"` of the current output.
+fn output_arg(comp: &StrataComp, name: &str) -> anyhow::Result<Output> {
+	match comp.workspaces.find_output(name) {
+		Some(output) => Ok(output.clone()),
+		None => anyhow::bail!("no output {}", name),
+	}
+}
+
+fn direction_arg(direction: String) -> anyhow::Result<Direction> {
+	Direction::from_str(&direction).map_err(|_| anyhow::anyhow!("invalid direction: {}", direction))
+}
+
 pub fn register<'gc>(ctx: lua::Context<'gc>, comp: Rc<RefCell<StrataComp>>) -> anyhow::Result<()> {
 	let index = lua::Table::new(&ctx);
 	index.set(ctx, "input", input::module(ctx, comp.clone())?)?;
@@ -20,21 +70,594 @@ pub fn register<'gc>(ctx: lua::Context<'gc>, comp: Rc<RefCell<StrataComp>>) -> a
 		ctx,
 		"spawn",
 		lua::Callback::from_fn(&ctx, |ctx, _, mut stack| {
-			let (cmd, _) = stack.consume::<(lua::String, lua::Value)>(ctx)?;
-			let _ = Command::new(cmd.to_str()?).spawn()?;
+			// either a program name or a list of program and arguments
+			let (cmd, _) = stack.consume::<(lua::Value, lua::Value)>(ctx)?;
+			let cmd = match cmd {
+				lua::Value::Table(_) => Vec::<String>::from_lua(ctx, cmd)?,
+				_ => vec![String::from_lua(ctx, cmd)?],
+			};
+			let (program, args) =
+				cmd.split_first().ok_or_else(|| anyhow::anyhow!("spawn: empty command"))?;
+			let _ = Command::new(program).args(args).spawn()?;
 
 			Ok(lua::CallbackReturn::Return)
 		}),
 	)?;
+
+	let quit_comp = comp.clone();
 	index.set(
 		ctx,
 		"quit",
-		lua::Callback::from_fn(&ctx, |ctx, _, mut stack| {
-			let comp = stack
-				.consume::<lua::UserData>(ctx)?
-				.downcast_static::<Rc<RefCell<StrataComp>>>()?;
+		lua::Callback::from_fn(&ctx, move |_, _, _| {
+			quit_comp.borrow_mut().quit();
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let close_comp = comp.clone();
+	index.set(
+		ctx,
+		"close_window",
+		lua::Callback::from_fn(&ctx, move |_, _, _| {
+			close_comp.borrow_mut().close_window();
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let set_floating_comp = comp.clone();
+	index.set(
+		ctx,
+		"set_floating",
+		lua::Callback::from_fn(&ctx, move |ctx, _, mut stack| {
+			let floating = stack.consume::<bool>(ctx)?;
+			set_floating_comp.borrow_mut().set_floating(floating);
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let toggle_floating_comp = comp.clone();
+	index.set(
+		ctx,
+		"toggle_floating",
+		lua::Callback::from_fn(&ctx, move |_, _, _| {
+			toggle_floating_comp.borrow_mut().toggle_floating();
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let center_comp = comp.clone();
+	index.set(
+		ctx,
+		"center_window",
+		lua::Callback::from_fn(&ctx, move |_, _, _| {
+			center_comp.borrow_mut().center_window();
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let set_fullscreen_comp = comp.clone();
+	index.set(
+		ctx,
+		"set_fullscreen",
+		lua::Callback::from_fn(&ctx, move |ctx, _, mut stack| {
+			let fullscreen = stack.consume::<bool>(ctx)?;
+			set_fullscreen_comp.borrow_mut().set_fullscreen(fullscreen);
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let toggle_fullscreen_comp = comp.clone();
+	index.set(
+		ctx,
+		"toggle_fullscreen",
+		lua::Callback::from_fn(&ctx, move |_, _, _| {
+			toggle_fullscreen_comp.borrow_mut().toggle_fullscreen();
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let set_maximized_comp = comp.clone();
+	index.set(
+		ctx,
+		"set_maximized",
+		lua::Callback::from_fn(&ctx, move |ctx, _, mut stack| {
+			let maximized = stack.consume::<bool>(ctx)?;
+			set_maximized_comp.borrow_mut().set_maximized(maximized);
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let toggle_maximized_comp = comp.clone();
+	index.set(
+		ctx,
+		"toggle_maximized",
+		lua::Callback::from_fn(&ctx, move |_, _, _| {
+			toggle_maximized_comp.borrow_mut().toggle_maximized();
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let focus_direction_comp = comp.clone();
+	index.set(
+		ctx,
+		"focus_direction",
+		lua::Callback::from_fn(&ctx, move |ctx, _, mut stack| {
+			let direction = direction_arg(stack.consume::<String>(ctx)?)?;
+			focus_direction_comp.borrow_mut().focus_direction(direction);
 
-			comp.borrow_mut().quit();
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let swap_direction_comp = comp.clone();
+	index.set(
+		ctx,
+		"swap_direction",
+		lua::Callback::from_fn(&ctx, move |ctx, _, mut stack| {
+			let direction = direction_arg(stack.consume::<String>(ctx)?)?;
+			swap_direction_comp.borrow_mut().swap_direction(direction);
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let move_direction_comp = comp.clone();
+	index.set(
+		ctx,
+		"move_direction",
+		lua::Callback::from_fn(&ctx, move |ctx, _, mut stack| {
+			let direction = direction_arg(stack.consume::<String>(ctx)?)?;
+			move_direction_comp.borrow_mut().move_direction(direction);
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let grow_comp = comp.clone();
+	index.set(
+		ctx,
+		"grow_window",
+		lua::Callback::from_fn(&ctx, move |ctx, _, mut stack| {
+			let (direction, amount) = stack.consume::<(String, Option<i32>)>(ctx)?;
+			let direction = direction_arg(direction)?;
+			grow_comp.borrow_mut().grow_window(direction, amount.unwrap_or(RESIZE_STEP));
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let shrink_comp = comp.clone();
+	index.set(
+		ctx,
+		"shrink_window",
+		lua::Callback::from_fn(&ctx, move |ctx, _, mut stack| {
+			let (direction, amount) = stack.consume::<(String, Option<i32>)>(ctx)?;
+			let direction = direction_arg(direction)?;
+			shrink_comp.borrow_mut().grow_window(direction, -amount.unwrap_or(RESIZE_STEP));
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let reset_ratios_comp = comp.clone();
+	index.set(
+		ctx,
+		"reset_ratios",
+		lua::Callback::from_fn(&ctx, move |ctx, _, mut stack| {
+			let id = stack.consume::<Option<u8>>(ctx)?;
+			let mut comp = reset_ratios_comp.borrow_mut();
+			let index = workspace_arg(&comp, id)?;
+			comp.workspaces.workspaces[index as usize].reset_ratios();
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let toggle_split_comp = comp.clone();
+	index.set(
+		ctx,
+		"toggle_split",
+		lua::Callback::from_fn(&ctx, move |_, _, _| {
+			toggle_split_comp.borrow_mut().toggle_split();
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let cycle_width_comp = comp.clone();
+	index.set(
+		ctx,
+		"cycle_width",
+		lua::Callback::from_fn(&ctx, move |_, _, _| {
+			cycle_width_comp.borrow_mut().cycle_width();
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let preselect_comp = comp.clone();
+	index.set(
+		ctx,
+		"preselect",
+		lua::Callback::from_fn(&ctx, move |ctx, _, mut stack| {
+			let (direction, ratio) = stack.consume::<(String, Option<f32>)>(ctx)?;
+			let direction = direction_arg(direction)?;
+			preselect_comp.borrow_mut().preselect(direction, ratio.unwrap_or(0.5));
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let cancel_preselection_comp = comp.clone();
+	index.set(
+		ctx,
+		"cancel_preselection",
+		lua::Callback::from_fn(&ctx, move |_, _, _| {
+			cancel_preselection_comp.borrow_mut().workspaces.current_mut().cancel_preselection();
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let promote_comp = comp.clone();
+	index.set(
+		ctx,
+		"promote_window",
+		lua::Callback::from_fn(&ctx, move |_, _, _| {
+			promote_comp.borrow_mut().promote_window();
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let focus_next_comp = comp.clone();
+	index.set(
+		ctx,
+		"focus_next",
+		lua::Callback::from_fn(&ctx, move |_, _, _| {
+			focus_next_comp.borrow_mut().focus_offset(1);
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let focus_prev_comp = comp.clone();
+	index.set(
+		ctx,
+		"focus_prev",
+		lua::Callback::from_fn(&ctx, move |_, _, _| {
+			focus_prev_comp.borrow_mut().focus_offset(-1);
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let switch_comp = comp.clone();
+	index.set(
+		ctx,
+		"switch_to_ws",
+		lua::Callback::from_fn(&ctx, move |ctx, _, mut stack| {
+			let id = stack.consume::<u8>(ctx)?;
+			let mut comp = switch_comp.borrow_mut();
+			let index = workspace_index(&comp, id)?;
+			comp.switch_to_workspace(index);
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let move_comp = comp.clone();
+	index.set(
+		ctx,
+		"move_window",
+		lua::Callback::from_fn(&ctx, move |ctx, _, mut stack| {
+			let id = stack.consume::<u8>(ctx)?;
+			let mut comp = move_comp.borrow_mut();
+			let index = workspace_index(&comp, id)?;
+			comp.move_window_to_workspace(index);
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let focus_output_comp = comp.clone();
+	index.set(
+		ctx,
+		"focus_output",
+		lua::Callback::from_fn(&ctx, move |ctx, _, mut stack| {
+			let name = stack.consume::<String>(ctx)?;
+			let mut comp = focus_output_comp.borrow_mut();
+			let output = output_arg(&comp, &name)?;
+			comp.focus_output(&output);
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	// strata.workspace.move_to_output("HDMI-A-1", workspace)
+	let move_workspace_comp = comp.clone();
+	index.set(
+		ctx,
+		"move_workspace_to_output",
+		lua::Callback::from_fn(&ctx, move |ctx, _, mut stack| {
+			let (name, id) = stack.consume::<(String, Option<u8>)>(ctx)?;
+			let mut comp = move_workspace_comp.borrow_mut();
+			let index = workspace_arg(&comp, id)?;
+			let output = output_arg(&comp, &name)?;
+			comp.move_workspace_to_output(index, &output);
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let move_to_output_comp = comp.clone();
+	index.set(
+		ctx,
+		"move_window_to_output",
+		lua::Callback::from_fn(&ctx, move |ctx, _, mut stack| {
+			let name = stack.consume::<String>(ctx)?;
+			let mut comp = move_to_output_comp.borrow_mut();
+			let output = output_arg(&comp, &name)?;
+			comp.move_window_to_output(&output);
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let follow_comp = comp.clone();
+	index.set(
+		ctx,
+		"move_window_and_follow",
+		lua::Callback::from_fn(&ctx, move |ctx, _, mut stack| {
+			let id = stack.consume::<u8>(ctx)?;
+			let mut comp = follow_comp.borrow_mut();
+			let index = workspace_index(&comp, id)?;
+			comp.follow_window_move(index);
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let set_config_comp = comp.clone();
+	index.set(
+		ctx,
+		"set_config",
+		lua::Callback::from_fn(&ctx, move |ctx, _, mut stack| {
+			let mut options = stack.consume::<Config>(ctx)?;
+			options.tiling.master = options.tiling.master.clamped();
+			let gaps = Gaps::from(&options.general).validate()?;
+
+			let mut comp = set_config_comp.borrow_mut();
+			let comp = &mut *comp;
+			// the bindings come first, so an invalid one leaves the running config untouched
+			comp.config.set_options(options)?;
+			comp.workspaces.set_gaps(gaps);
+			comp.workspaces.set_tiling(&comp.config.options.tiling);
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let get_config_comp = comp.clone();
+	index.set(
+		ctx,
+		"get_config",
+		lua::Callback::from_fn(&ctx, move |ctx, _, mut stack| {
+			stack.push_front(get_config_comp.borrow().config.options.to_lua(ctx));
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let update_config_comp = comp.clone();
+	index.set(
+		ctx,
+		"update_config",
+		lua::Callback::from_fn(&ctx, move |ctx, _, mut stack| {
+			let value = stack.consume::<lua::Value>(ctx)?;
+
+			let mut comp = update_config_comp.borrow_mut();
+			let comp = &mut *comp;
+			// update a copy so a type error halfway through doesn't leave a partial update
+			let mut options = comp.config.options.clone();
+			options.update_from_lua(ctx, value)?;
+			options.tiling.master = options.tiling.master.clamped();
+			// only touch the gaps when they changed, keeping the ones set per workspace
+			let gaps = Gaps::from(&options.general).validate()?;
+			let gaps_changed = gaps != Gaps::from(&comp.config.options.general);
+			let tiling_changed = options.tiling != comp.config.options.tiling;
+			comp.config.set_options(options)?;
+			if gaps_changed {
+				comp.workspaces.set_gaps(gaps);
+			}
+			if tiling_changed {
+				comp.workspaces.set_tiling(&comp.config.options.tiling);
+			}
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	// strata.set_gaps({ inner = 4, outer = 8, smart = true }, workspace)
+	let set_gaps_comp = comp.clone();
+	index.set(
+		ctx,
+		"set_gaps",
+		lua::Callback::from_fn(&ctx, move |ctx, _, mut stack| {
+			let (value, id) = stack.consume::<(lua::Value, lua::Value)>(ctx)?;
+
+			let mut comp = set_gaps_comp.borrow_mut();
+			let index = workspace_arg(&comp, Option::<u8>::from_lua(ctx, id)?)?;
+			let workspace = &mut comp.workspaces.workspaces[index as usize];
+			let mut gaps = workspace.gaps;
+			gaps.update_from_lua(ctx, value)?;
+			workspace.gaps = gaps.validate()?;
+			refresh_geometry(workspace);
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let set_layout_comp = comp.clone();
+	index.set(
+		ctx,
+		"set_layout",
+		lua::Callback::from_fn(&ctx, move |ctx, _, mut stack| {
+			let (layout, id) = stack.consume::<(lua::Value, lua::Value)>(ctx)?;
+			let layout = LayoutKind::from_lua(ctx, layout)?;
+
+			let mut comp = set_layout_comp.borrow_mut();
+			let index = workspace_arg(&comp, Option::<u8>::from_lua(ctx, id)?)?;
+			if let LayoutKind::Custom(name) = &layout {
+				if !comp.config.layouts.contains_key(name) {
+					return Err(anyhow::anyhow!("no layout named {}", name).into());
+				}
+			}
+			let tiling = comp.config.options.tiling.clone();
+			comp.workspaces.workspaces[index as usize].set_layout(layout, &tiling);
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let get_layout_comp = comp.clone();
+	index.set(
+		ctx,
+		"get_layout",
+		lua::Callback::from_fn(&ctx, move |ctx, _, mut stack| {
+			let id = stack.consume::<lua::Value>(ctx)?;
+
+			let comp = get_layout_comp.borrow();
+			let index = workspace_arg(&comp, Option::<u8>::from_lua(ctx, id)?)?;
+			stack.push_front(comp.workspaces.workspaces[index as usize].layout.kind().to_lua(ctx));
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	// strata.register_layout("columns", function(area, windows, state) ... end)
+	let register_layout_comp = comp.clone();
+	index.set(
+		ctx,
+		"register_layout",
+		lua::Callback::from_fn(&ctx, move |ctx, _, mut stack| {
+			let (name, function) = stack.consume::<(String, lua::Function)>(ctx)?;
+			if !matches!(LayoutKind::from_name(&name), LayoutKind::Custom(_)) {
+				return Err(anyhow::anyhow!("{} is a builtin layout", name).into());
+			}
+
+			let mut comp = register_layout_comp.borrow_mut();
+			comp.config.layouts.insert(name.clone(), ctx.stash(function));
+			// workspaces already using it run the new function
+			for workspace in comp.workspaces.iter() {
+				if let Some(custom) = workspace.layout.custom().filter(|c| c.name() == name) {
+					custom.invalidate();
+				}
+			}
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	// strata.set_master({ count = 2, ratio = 0.6, side = "right" }, workspace)
+	let set_master_comp = comp.clone();
+	index.set(
+		ctx,
+		"set_master",
+		lua::Callback::from_fn(&ctx, move |ctx, _, mut stack| {
+			let (value, id) = stack.consume::<(lua::Value, lua::Value)>(ctx)?;
+
+			let mut comp = set_master_comp.borrow_mut();
+			let index = workspace_arg(&comp, Option::<u8>::from_lua(ctx, id)?)?;
+			let workspace = &mut comp.workspaces.workspaces[index as usize];
+			let master = workspace
+				.layout
+				.master()
+				.ok_or_else(|| anyhow::anyhow!("workspace {} has no master area", index + 1))?;
+			let mut updated = *master;
+			updated.update_from_lua(ctx, value)?;
+			*master = updated.clamped();
+			refresh_geometry(workspace);
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let get_master_comp = comp.clone();
+	index.set(
+		ctx,
+		"get_master",
+		lua::Callback::from_fn(&ctx, move |ctx, _, mut stack| {
+			let id = stack.consume::<lua::Value>(ctx)?;
+
+			let mut comp = get_master_comp.borrow_mut();
+			let index = workspace_arg(&comp, Option::<u8>::from_lua(ctx, id)?)?;
+			let master = comp.workspaces.workspaces[index as usize].layout.master().copied();
+			stack.push_front(master.to_lua(ctx));
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let inc_master_count_comp = comp.clone();
+	index.set(
+		ctx,
+		"inc_master_count",
+		lua::Callback::from_fn(&ctx, move |ctx, _, mut stack| {
+			let delta = stack.consume::<i32>(ctx)?;
+			inc_master_count_comp.borrow_mut().workspaces.current_mut().inc_master_count(delta);
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let inc_master_ratio_comp = comp.clone();
+	index.set(
+		ctx,
+		"inc_master_ratio",
+		lua::Callback::from_fn(&ctx, move |ctx, _, mut stack| {
+			let delta = stack.consume::<f32>(ctx)?;
+			inc_master_ratio_comp.borrow_mut().workspaces.current_mut().inc_master_ratio(delta);
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let get_gaps_comp = comp.clone();
+	index.set(
+		ctx,
+		"get_gaps",
+		lua::Callback::from_fn(&ctx, move |ctx, _, mut stack| {
+			let id = stack.consume::<lua::Value>(ctx)?;
+
+			let comp = get_gaps_comp.borrow();
+			let index = workspace_arg(&comp, Option::<u8>::from_lua(ctx, id)?)?;
+			stack.push_front(comp.workspaces.workspaces[index as usize].gaps.to_lua(ctx));
+
+			Ok(lua::CallbackReturn::Return)
+		}),
+	)?;
+
+	let reload_comp = comp.clone();
+	index.set(
+		ctx,
+		"reload",
+		lua::Callback::from_fn(&ctx, move |_, _, _| {
+			// the config can't be re-evaluated from inside the running Lua callback, so defer it
+			reload_comp.borrow().loop_handle.insert_idle(|state| {
+				if let Err(err) = state.reload_config() {
+					error!("Failed to reload config, keeping the previous one: {:#}", err);
+				}
+			});
 
 			Ok(lua::CallbackReturn::Return)
 		}),
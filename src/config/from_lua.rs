@@ -1,32 +1,55 @@
-// use strata_core::UpdateFromLua;
-//
-// use super::{
-// 	LuaFunction,
-// 	Rule,
-// };
-//
-// #[derive(Debug, Default)]
-// pub(super) struct Rules {
-// 	pub list: Vec<Rule>,
-// }
-//
-// impl Rules {
-// 	pub fn add_sequence(&mut self, rules: Table, lua: &Lua) -> mlua::Result<()> {
-// 		for value in rules.sequence_values::<Table>() {
-// 			let value = value?;
-// 			if value.contains_key("triggers")? {
-// 				self.list.push(Rule::from_lua(Value::Table(value), lua)?);
-// 			} else {
-// 				self.add_sequence(value, lua)?;
-// 			}
-// 		}
-//
-// 		Ok(())
-// 	}
-// }
-//
-// impl Into<Vec<Rule>> for Rules {
-// 	fn into(self) -> Vec<Rule> {
-// 		self.list
-// 	}
-// }
+use piccolo::{
+	self as lua,
+};
+use strata_core::FromLua;
+
+use super::Rule;
+
+/// Rules may be nested in lists (e.g. the output of `strata.rules.bind_to_workspace`),
+/// this flattens them into a single list.
+#[derive(Debug, Default)]
+pub(super) struct Rules {
+	pub list: Vec<Rule>,
+}
+
+impl Rules {
+	pub fn add_sequence<'gc>(
+		&mut self,
+		ctx: lua::Context<'gc>,
+		rules: lua::Table<'gc>,
+	) -> Result<(), lua::TypeError> {
+		for i in 1..=rules.length() {
+			let value = rules.get(ctx, i);
+			let lua::Value::Table(table) = value else {
+				return Err(lua::TypeError { expected: "table", found: value.type_name() });
+			};
+
+			if let lua::Value::Nil = table.get(ctx, "triggers") {
+				self.add_sequence(ctx, table)?;
+			} else {
+				self.list.push(Rule::from_lua(ctx, value)?);
+			}
+		}
+
+		Ok(())
+	}
+}
+
+impl<'gc> FromLua<'gc> for Rules {
+	fn from_lua(ctx: lua::Context<'gc>, value: lua::Value<'gc>) -> Result<Self, lua::TypeError> {
+		let lua::Value::Table(table) = value else {
+			return Err(lua::TypeError { expected: "table", found: value.type_name() });
+		};
+
+		let mut rules = Rules::default();
+		rules.add_sequence(ctx, table)?;
+
+		Ok(rules)
+	}
+}
+
+impl From<Rules> for Vec<Rule> {
+	fn from(rules: Rules) -> Self {
+		rules.list
+	}
+}
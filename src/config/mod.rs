@@ -1,6 +1,7 @@
 mod from_lua;
 mod parse;
+mod reload;
 mod structs;
 
-// pub use parse::parse_config;
-// pub use structs::*;
+pub use parse::ConfigPaths;
+pub use structs::*;
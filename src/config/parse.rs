@@ -1 +1,204 @@
+// Copyright 2023 the Strata authors
+// SPDX-License-Identifier: GPL-3.0-or-later
 
+use std::{
+	fs,
+	path::{
+		Path,
+		PathBuf,
+	},
+};
+
+use log::{
+	error,
+	info,
+	warn,
+};
+use piccolo::{
+	self as lua,
+};
+
+use crate::{
+	state::{
+		StrataConfig,
+		StrataState,
+	},
+	Args,
+};
+
+const PRELUDE: &str = include_str!("prelude.lua");
+
+// used when neither the user's config nor the installed default could be evaluated
+const BUILTIN_CONFIG: &str = r#"
+local Key = strata.input.Key
+local Mod = strata.input.Mod
+
+local _ = Key({ Mod.Control_L, Mod.Alt_L }, Key.Return, function()
+	strata.spawn('kitty')
+end)
+
+local _ = Key({ Mod.Control_L, Mod.Alt_L }, Key.Escape, function()
+	strata:quit()
+end)
+"#;
+
+pub struct ConfigPaths {
+	/// Config files to try, in order, until one evaluates without errors.
+	pub candidates: Vec<PathBuf>,
+	/// Directory holding the shipped `strata` Lua modules.
+	pub lib_dir: Option<PathBuf>,
+}
+
+impl ConfigPaths {
+	pub fn find(args: &Args) -> Self {
+		let xdg = xdg::BaseDirectories::with_prefix("strata").ok();
+		let mut candidates = Vec::new();
+
+		if let Some(path) = &args.config {
+			candidates.push(path.clone());
+		} else if let Some(path) = xdg.as_ref().and_then(|xdg| xdg.find_config_file("config.lua")) {
+			candidates.push(path);
+		}
+
+		if let Some(path) = xdg.as_ref().and_then(|xdg| xdg.find_data_file("strata.default.lua")) {
+			candidates.push(path);
+		}
+
+		let lib_dir = xdg.as_ref().and_then(|xdg| xdg.find_data_file("lua"));
+
+		ConfigPaths { candidates, lib_dir }
+	}
+
+	/// Builds the `package.path` used by `require`: the shipped library first, then the
+	/// directories of the config files so user configs can be split into modules.
+	pub fn package_path(&self) -> String {
+		let mut dirs: Vec<&Path> = Vec::new();
+		if let Some(lib_dir) = &self.lib_dir {
+			dirs.push(lib_dir);
+		}
+		dirs.extend(self.candidates.iter().filter_map(|path| path.parent()));
+
+		let mut templates = Vec::new();
+		for dir in dirs {
+			let dir = dir.display();
+			templates.push(format!("{dir}/?.lua"));
+			templates.push(format!("{dir}/?/init.lua"));
+			templates.push(format!("{dir}/strata/?.lua"));
+		}
+		templates.dedup();
+
+		templates.join(";")
+	}
+}
+
+fn search_path(name: &str, path: &str) -> Result<PathBuf, String> {
+	let name = name.replace('.', "/");
+	let mut tried = String::new();
+
+	for template in path.split(';').filter(|t| !t.is_empty()) {
+		let candidate = PathBuf::from(template.replace('?', &name));
+		if candidate.is_file() {
+			return Ok(candidate);
+		}
+		tried.push_str(&format!("\n\tno file '{}'", candidate.display()));
+	}
+
+	Err(tried)
+}
+
+pub fn init_package(lua_vm: &mut lua::Lua, package_path: &str) -> anyhow::Result<()> {
+	let ex = lua_vm.try_enter(|ctx| {
+		let loaded = lua::Table::new(&ctx);
+		loaded.set(ctx, "strata.api", ctx.globals().get(ctx, "strata"))?;
+
+		let package = lua::Table::new(&ctx);
+		package.set(ctx, "path", ctx.intern(package_path.as_bytes()))?;
+		package.set(ctx, "loaded", loaded)?;
+		package.set(
+			ctx,
+			"searchpath",
+			lua::Callback::from_fn(&ctx, |ctx, _, mut stack| {
+				let (name, path) = stack.consume::<(lua::String, lua::String)>(ctx)?;
+
+				match search_path(name.to_str()?, path.to_str()?) {
+					Ok(found) => {
+						let found = found.to_string_lossy();
+						stack.push_back(lua::Value::String(ctx.intern(found.as_bytes())));
+					}
+					Err(tried) => {
+						stack.push_back(lua::Value::Nil);
+						stack.push_back(lua::Value::String(ctx.intern(tried.as_bytes())));
+					}
+				}
+
+				Ok(lua::CallbackReturn::Return)
+			}),
+		)?;
+		ctx.globals().set(ctx, "package", package)?;
+
+		ctx.globals().set(
+			ctx,
+			"loadfile",
+			lua::Callback::from_fn(&ctx, |ctx, _, mut stack| {
+				let path = stack.consume::<lua::String>(ctx)?;
+				let path = path.to_str()?;
+				let source = fs::read(path)?;
+				let chunk = lua::Closure::load(ctx, Some(path), &source[..])?;
+
+				stack.push_back(lua::Value::Function(chunk.into()));
+
+				Ok(lua::CallbackReturn::Return)
+			}),
+		)?;
+
+		let prelude = lua::Closure::load(ctx, Some("prelude"), PRELUDE.as_bytes())?;
+
+		Ok(ctx.stash(lua::Executor::start(ctx, prelude.into(), ())))
+	})?;
+
+	lua_vm.execute::<()>(&ex)?;
+
+	Ok(())
+}
+
+pub fn parse_config(lua_vm: &mut lua::Lua, name: &str, source: &[u8]) -> anyhow::Result<()> {
+	let ex = lua_vm.try_enter(|ctx| {
+		let main = lua::Closure::load(ctx, Some(name), source)?;
+
+		Ok(ctx.stash(lua::Executor::start(ctx, main.into(), ())))
+	})?;
+
+	lua_vm.execute::<()>(&ex)?;
+
+	Ok(())
+}
+
+impl StrataState {
+	pub fn load_config(&mut self) {
+		if let Err(err) = init_package(&mut self.lua, &self.config_paths.package_path()) {
+			error!("Failed to set up the Lua package loader: {:#}", err);
+		}
+
+		for path in &self.config_paths.candidates {
+			info!("Loading config from {}", path.display());
+
+			let res = fs::read(path)
+				.map_err(anyhow::Error::from)
+				.and_then(|source| parse_config(&mut self.lua, &path.to_string_lossy(), &source));
+
+			match res {
+				Ok(()) => return,
+				Err(err) => {
+					error!("Failed to load config {}: {:#}", path.display(), err);
+					// drop whatever the broken config managed to register before failing
+					self.comp.borrow_mut().config.keybinds.clear();
+				}
+			}
+		}
+
+		warn!("No usable config found, falling back to the builtin defaults");
+		if let Err(err) = parse_config(&mut self.lua, "builtin", BUILTIN_CONFIG.as_bytes()) {
+			error!("Failed to load the builtin config: {:#}", err);
+		}
+	}
+}
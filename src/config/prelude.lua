@@ -0,0 +1,23 @@
+-- Copyright 2023 the Strata authors
+-- SPDX-License-Identifier: GPL-3.0-or-later
+
+-- Minimal `require`, resolving modules through `package.path` like stock Lua does.
+function require(name)
+	local module = package.loaded[name]
+	if module ~= nil then
+		return module
+	end
+
+	local path, err = package.searchpath(name, package.path)
+	if path == nil then
+		error("module '" .. name .. "' not found:" .. err)
+	end
+
+	module = loadfile(path)(name, path)
+	if module == nil then
+		module = true
+	end
+	package.loaded[name] = module
+
+	return module
+end
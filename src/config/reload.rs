@@ -0,0 +1,126 @@
+// Copyright 2023 the Strata authors
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+	fs,
+	io::ErrorKind,
+	path::Path,
+};
+
+use inotify::{
+	Inotify,
+	WatchMask,
+};
+use log::{
+	error,
+	info,
+};
+use smithay::reexports::calloop::{
+	generic::Generic,
+	Interest,
+	Mode,
+	PostAction,
+};
+
+use super::parse::{
+	init_package,
+	parse_config,
+};
+use crate::state::{
+	StrataConfig,
+	StrataState,
+};
+
+impl StrataState {
+	/// Re-evaluates the config file. If the new one fails, the previous config stays active,
+	/// along with the gaps, layouts and master settings of the workspaces.
+	pub fn reload_config(&mut self) -> anyhow::Result<()> {
+		let path = self
+			.config_paths
+			.candidates
+			.first()
+			.cloned()
+			.ok_or_else(|| anyhow::anyhow!("no config file to reload"))?;
+		info!("Reloading config from {}", path.display());
+		let source = fs::read(&path)?;
+
+		let (backup, snapshots) = {
+			let mut comp = self.comp.borrow_mut();
+			let snapshots: Vec<_> = comp.workspaces.iter().map(|w| w.snapshot()).collect();
+			(std::mem::replace(&mut comp.config, StrataConfig::default()), snapshots)
+		};
+
+		let res = init_package(&mut self.lua, &self.config_paths.package_path())
+			.and_then(|()| parse_config(&mut self.lua, &path.to_string_lossy(), &source));
+		if let Err(err) = res {
+			let mut comp = self.comp.borrow_mut();
+			let tiling = backup.options.tiling.clone();
+			comp.config = backup;
+			for (workspace, snapshot) in comp.workspaces.iter().zip(snapshots) {
+				workspace.restore(snapshot, &tiling);
+			}
+			return Err(err);
+		}
+
+		// options the new config leaves out are back to their defaults, so the workspaces follow
+		let mut comp = self.comp.borrow_mut();
+		let comp = &mut *comp;
+		comp.workspaces.apply_config(&comp.config.options.general, &comp.config.options.tiling);
+		comp.schedule_render();
+
+		Ok(())
+	}
+
+	pub fn init_config_watcher(&mut self) {
+		if let Err(err) = self.watch_config_dir() {
+			error!("Failed to watch the config directory: {:#}", err);
+		}
+	}
+
+	/// Reloads the config whenever a Lua file next to it is written, moved in or deleted.
+	fn watch_config_dir(&mut self) -> anyhow::Result<()> {
+		let dir = self
+			.config_paths
+			.candidates
+			.first()
+			.and_then(|path| path.parent())
+			.ok_or_else(|| anyhow::anyhow!("no config directory"))?;
+
+		let inotify = Inotify::init()?;
+		inotify
+			.watches()
+			.add(dir, WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO | WatchMask::DELETE)?;
+
+		let mut buffer = [0; 4096];
+		self.comp
+			.borrow()
+			.loop_handle
+			.insert_source(
+				Generic::new(inotify, Interest::READ, Mode::Level),
+				move |_, inotify, state| {
+					// SAFETY: the inotify instance is only read from, never replaced or closed
+					let events = match unsafe { inotify.get_mut() }.read_events(&mut buffer) {
+						Ok(events) => events,
+						Err(err) if err.kind() == ErrorKind::WouldBlock => {
+							return Ok(PostAction::Continue);
+						}
+						Err(err) => return Err(err),
+					};
+					let changed = events
+						.filter_map(|event| event.name)
+						.any(|name| Path::new(name).extension().is_some_and(|ext| ext == "lua"));
+
+					if changed {
+						if let Err(err) = state.reload_config() {
+							error!("Failed to reload config, keeping the previous one: {:#}", err);
+						}
+					}
+
+					Ok(PostAction::Continue)
+				},
+			)
+			.map_err(|err| err.error)?;
+
+		Ok(())
+	}
+}
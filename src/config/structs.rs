@@ -1,146 +1,393 @@
-// use mlua::{
-// 	FromLua,
-// 	Function,
-// 	RegistryKey,
-// };
-// use smart_default::SmartDefault;
-// use strata_derive::Config;
-// use strum::EnumString;
-//
-// use super::from_lua;
-//
-// #[derive(Debug, Default, Config)]
-// pub struct Config {
-// 	pub autostart: Vec<Cmd>,
-// 	pub general: General,
-// 	pub decorations: WindowDecorations,
-// 	pub tiling: Tiling,
-// 	pub animations: Animations,
-// 	pub bindings: Vec<Keybinding>,
-// 	#[config(from = from_lua::Rules)]
-// 	pub rules: Vec<Rule>,
-// }
-//
-// #[derive(Debug)]
-// pub struct LuaFunction {
-// 	pub(crate) key: RegistryKey,
-// }
-//
-// impl LuaFunction {
-// 	pub fn call<'lua>(&'lua self, lua: &'lua mlua::Lua) -> anyhow::Result<()> {
-// 		lua.registry_value::<Function>(&self.key)?.call(0)?;
-// 		Ok(())
-// 	}
-// }
-//
-// pub type Cmd = Vec<String>;
-//
-// #[derive(Debug, SmartDefault, Config)]
-// pub struct General {
-// 	#[default(10)]
-// 	pub workspaces: u8,
-// 	#[default(5)]
-// 	pub gaps_in: i32,
-// 	#[default(10)]
-// 	pub gaps_out: i32,
-// 	pub kb_repeat: Vec<i32>,
-// }
-//
-// #[derive(Debug, Default, Config)]
-// pub struct WindowDecorations {
-// 	pub border: Border,
-// 	pub window: Window,
-// 	pub blur: Blur,
-// 	pub shadow: Shadow,
-// }
-//
-// #[derive(Debug, SmartDefault, Config)]
-// pub struct Border {
-// 	pub enable: bool,
-// 	#[default(2)]
-// 	pub width: u32,
-// 	#[default("#ffffff")]
-// 	pub active: String,
-// 	#[default("#888888")]
-// 	pub inactive: String,
-// 	#[default(5.0)]
-// 	pub radius: f64,
-// }
-//
-// #[derive(Debug, SmartDefault, Config)]
-// pub struct Window {
-// 	#[default(1.0)]
-// 	pub opacity: f64,
-// }
-//
-// #[derive(Debug, SmartDefault, Config)]
-// pub struct Blur {
-// 	pub enable: bool,
-// 	#[default(5)]
-// 	pub size: u32,
-// 	#[default(1)]
-// 	pub passes: u32,
-// 	#[default(true)]
-// 	pub optimize: bool,
-// }
-//
-// #[derive(Debug, SmartDefault, Config)]
-// pub struct Shadow {
-// 	pub enable: bool,
-// 	#[default(5)]
-// 	pub size: u32,
-// 	#[default(5)]
-// 	pub blur: u32,
-// 	#[default("#000000")]
-// 	pub color: String,
-// }
-//
-// #[derive(Debug, Default, Config)]
-// pub struct Tiling {
-// 	pub layout: Layout,
-// }
-//
-// #[derive(Debug, Default, EnumString, Config)]
-// #[strum(serialize_all = "snake_case")]
-// pub enum Layout {
-// 	#[default]
-// 	Dwindle,
-// }
-//
-// #[derive(Debug, SmartDefault, Config)]
-// pub struct Animations {
-// 	#[default(true)]
-// 	pub enable: bool,
-// }
-//
-// #[derive(Debug, Config)]
-// pub struct Keybinding {
-// 	#[config(flat)]
-// 	pub keys: Vec<String>,
-// 	#[config(flat)]
-// 	pub action: LuaFunction,
-// }
-//
-// #[derive(Debug, Config)]
-// pub struct Rule {
-// 	#[config(flat)]
-// 	pub triggers: Vec<Trigger>,
-// 	#[config(flat)]
-// 	pub action: LuaFunction,
-// }
-//
-// #[derive(Debug, Config)]
-// pub struct Trigger {
-// 	#[config(flat)]
-// 	pub event: String,
-// 	#[config(flat)]
-// 	pub class_name: Option<String>,
-// 	#[config(flat)]
-// 	pub workspace: Option<i32>,
-// }
-//
-// impl Config {
-// 	pub fn set(&mut self, config: Config) {
-// 		*self = config;
-// 	}
-// }
+use std::fmt;
+
+use piccolo::{
+	self as lua,
+	FromValue,
+};
+use smart_default::SmartDefault;
+use strata_core::{
+	FromLua,
+	ToLua,
+	UpdateFromLua,
+};
+use strata_derive::Config;
+use strum::{
+	AsRefStr,
+	EnumString,
+};
+
+use super::from_lua;
+use crate::layouts::dwindle::{
+	MAX_RATIO,
+	MIN_RATIO,
+};
+
+#[derive(Debug, Clone, Default, Config)]
+pub struct Config {
+	pub autostart: Vec<Cmd>,
+	pub general: General,
+	pub decorations: WindowDecorations,
+	pub tiling: Tiling,
+	pub mouse: Mouse,
+	pub focus: Focus,
+	pub animations: Animations,
+	pub bindings: Vec<Keybinding>,
+	#[config(from = from_lua::Rules)]
+	pub rules: Vec<Rule>,
+}
+
+#[derive(Clone)]
+pub struct LuaFunction {
+	pub(crate) key: lua::StashedFunction,
+}
+
+impl fmt::Debug for LuaFunction {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("LuaFunction")
+	}
+}
+
+impl<'gc> FromLua<'gc> for LuaFunction {
+	fn from_lua(ctx: lua::Context<'gc>, value: lua::Value<'gc>) -> Result<Self, lua::TypeError> {
+		Ok(LuaFunction { key: ctx.stash(lua::Function::from_value(ctx, value)?) })
+	}
+}
+
+impl<'gc> UpdateFromLua<'gc> for LuaFunction {
+	fn update_from_lua(
+		&mut self,
+		ctx: lua::Context<'gc>,
+		value: lua::Value<'gc>,
+	) -> Result<(), lua::TypeError> {
+		*self = Self::from_lua(ctx, value)?;
+		Ok(())
+	}
+}
+
+impl ToLua for LuaFunction {
+	fn to_lua<'gc>(&self, ctx: lua::Context<'gc>) -> lua::Value<'gc> {
+		lua::Value::Function(ctx.fetch(&self.key))
+	}
+}
+
+pub type Cmd = Vec<String>;
+
+#[derive(Debug, Clone, SmartDefault, Config)]
+pub struct General {
+	#[default(10)]
+	pub workspaces: u8,
+	#[default(5)]
+	pub gaps_in: i32,
+	#[default(10)]
+	pub gaps_out: i32,
+	pub smart_gaps: bool,
+	pub kb_repeat: Vec<i32>,
+}
+
+#[derive(Debug, Clone, Default, Config)]
+pub struct WindowDecorations {
+	pub border: Border,
+	pub window: Window,
+	pub blur: Blur,
+	pub shadow: Shadow,
+}
+
+#[derive(Debug, Clone, SmartDefault, Config)]
+pub struct Border {
+	pub enable: bool,
+	#[default(2)]
+	pub width: u32,
+	#[default(Color::rgb(0xff, 0xff, 0xff))]
+	pub active: Color,
+	#[default(Color::rgb(0x88, 0x88, 0x88))]
+	pub inactive: Color,
+	#[default(5.0)]
+	pub radius: f64,
+	pub gradient: Gradient,
+}
+
+/// Borders fade from `active`/`inactive` to these colors; without one they're solid.
+#[derive(Debug, Clone, SmartDefault, Config)]
+pub struct Gradient {
+	pub active: Option<Color>,
+	pub inactive: Option<Color>,
+	/// In degrees.
+	#[default(45.0)]
+	pub angle: f64,
+}
+
+/// An RGB color, written as `"#rgb"` or `"#rrggbb"` in the config.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color(pub [f32; 3]);
+
+impl Color {
+	pub fn rgb(r: u8, g: u8, b: u8) -> Self {
+		Color([r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0])
+	}
+
+	pub fn from_hex(hex: &str) -> Option<Self> {
+		let hex = hex.strip_prefix('#').unwrap_or(hex);
+		let digits =
+			hex.chars().map(|c| c.to_digit(16).map(|d| d as u8)).collect::<Option<Vec<_>>>()?;
+
+		match digits[..] {
+			[r, g, b] => Some(Color::rgb(r * 0x11, g * 0x11, b * 0x11)),
+			[r1, r2, g1, g2, b1, b2] => {
+				Some(Color::rgb((r1 << 4) | r2, (g1 << 4) | g2, (b1 << 4) | b2))
+			}
+			_ => None,
+		}
+	}
+
+	pub fn to_hex(self) -> String {
+		let [r, g, b] = self.0.map(|c| (c * 255.0).round() as u8);
+		format!("#{:02x}{:02x}{:02x}", r, g, b)
+	}
+}
+
+impl<'gc> FromLua<'gc> for Color {
+	fn from_lua(ctx: lua::Context<'gc>, value: lua::Value<'gc>) -> Result<Self, lua::TypeError> {
+		let hex = String::from_lua(ctx, value)?;
+		Color::from_hex(&hex).ok_or(lua::TypeError { expected: "hex color", found: "string" })
+	}
+}
+
+impl<'gc> UpdateFromLua<'gc> for Color {
+	fn update_from_lua(
+		&mut self,
+		ctx: lua::Context<'gc>,
+		value: lua::Value<'gc>,
+	) -> Result<(), lua::TypeError> {
+		*self = Self::from_lua(ctx, value)?;
+		Ok(())
+	}
+}
+
+impl ToLua for Color {
+	fn to_lua<'gc>(&self, ctx: lua::Context<'gc>) -> lua::Value<'gc> {
+		self.to_hex().to_lua(ctx)
+	}
+}
+
+#[derive(Debug, Clone, SmartDefault, Config)]
+pub struct Window {
+	#[default(1.0)]
+	pub opacity: f64,
+}
+
+#[derive(Debug, Clone, SmartDefault, Config)]
+pub struct Blur {
+	pub enable: bool,
+	#[default(5)]
+	pub size: u32,
+	#[default(1)]
+	pub passes: u32,
+	#[default(true)]
+	pub optimize: bool,
+}
+
+#[derive(Debug, Clone, SmartDefault, Config)]
+pub struct Shadow {
+	pub enable: bool,
+	#[default(5)]
+	pub size: u32,
+	#[default(5)]
+	pub blur: u32,
+	#[default("#000000")]
+	pub color: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Config)]
+pub struct Tiling {
+	pub layout: LayoutKind,
+	pub master: Master,
+	pub monocle: Monocle,
+	pub scrolling: Scrolling,
+}
+
+/// A builtin layout, or one registered from Lua with `strata.register_layout`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum LayoutKind {
+	#[default]
+	Dwindle,
+	MasterStack,
+	Monocle,
+	Scrolling,
+	Grid,
+	Spiral,
+	Custom(String),
+}
+
+impl LayoutKind {
+	pub fn from_name(name: &str) -> Self {
+		match name {
+			"dwindle" => LayoutKind::Dwindle,
+			"master_stack" => LayoutKind::MasterStack,
+			"monocle" => LayoutKind::Monocle,
+			"scrolling" => LayoutKind::Scrolling,
+			"grid" => LayoutKind::Grid,
+			"spiral" => LayoutKind::Spiral,
+			name => LayoutKind::Custom(name.to_string()),
+		}
+	}
+
+	pub fn name(&self) -> &str {
+		match self {
+			LayoutKind::Dwindle => "dwindle",
+			LayoutKind::MasterStack => "master_stack",
+			LayoutKind::Monocle => "monocle",
+			LayoutKind::Scrolling => "scrolling",
+			LayoutKind::Grid => "grid",
+			LayoutKind::Spiral => "spiral",
+			LayoutKind::Custom(name) => name,
+		}
+	}
+}
+
+impl<'gc> FromLua<'gc> for LayoutKind {
+	fn from_lua(ctx: lua::Context<'gc>, value: lua::Value<'gc>) -> Result<Self, lua::TypeError> {
+		Ok(LayoutKind::from_name(&String::from_lua(ctx, value)?))
+	}
+}
+
+impl<'gc> UpdateFromLua<'gc> for LayoutKind {
+	fn update_from_lua(
+		&mut self,
+		ctx: lua::Context<'gc>,
+		value: lua::Value<'gc>,
+	) -> Result<(), lua::TypeError> {
+		*self = Self::from_lua(ctx, value)?;
+		Ok(())
+	}
+}
+
+impl ToLua for LayoutKind {
+	fn to_lua<'gc>(&self, ctx: lua::Context<'gc>) -> lua::Value<'gc> {
+		self.name().to_string().to_lua(ctx)
+	}
+}
+
+/// The master stack layout puts `count` windows on the `side` of the output, taking `ratio`
+/// of it, and stacks the other windows next to them.
+#[derive(Debug, Clone, Copy, PartialEq, SmartDefault, Config)]
+pub struct Master {
+	#[default(1)]
+	pub count: u32,
+	#[default(0.55)]
+	pub ratio: f32,
+	pub side: MasterSide,
+}
+
+impl Master {
+	/// Brings `ratio` within the range `inc_master_ratio` keeps it in.
+	pub fn clamped(self) -> Self {
+		let ratio = match self.ratio {
+			ratio if ratio.is_nan() => Master::default().ratio,
+			ratio => ratio.clamp(MIN_RATIO, MAX_RATIO),
+		};
+		Master { ratio, ..self }
+	}
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, EnumString, AsRefStr, Config)]
+#[strum(serialize_all = "snake_case")]
+pub enum MasterSide {
+	#[default]
+	Left,
+	Right,
+	Top,
+	Bottom,
+}
+
+/// The monocle layout shows one window at a time, taking the whole output. With `tab_bar`
+/// set, the titles of all its windows are listed above it, in tabs `tab_height` pixels high.
+#[derive(Debug, Clone, Copy, PartialEq, SmartDefault, Config)]
+pub struct Monocle {
+	#[default(true)]
+	pub tab_bar: bool,
+	#[default(24)]
+	pub tab_height: i32,
+}
+
+/// The scrolling layout puts windows in columns on a strip that scrolls sideways to keep the
+/// focused column in view. Columns are as wide as one of `widths`, a share of the output
+/// each, and start out with the first one.
+#[derive(Debug, Clone, PartialEq, SmartDefault, Config)]
+pub struct Scrolling {
+	#[default(vec![0.5, 2.0 / 3.0, 1.0])]
+	pub widths: Vec<f32>,
+}
+
+/// Dragging a window with `modifier` held moves it with `move_button` and resizes it with
+/// `resize_button`.
+#[derive(Debug, Clone, SmartDefault, Config)]
+pub struct Mouse {
+	#[default("Super_L")]
+	pub modifier: String,
+	#[default(MouseButton::Left)]
+	pub move_button: MouseButton,
+	#[default(MouseButton::Right)]
+	pub resize_button: MouseButton,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, EnumString, AsRefStr, Config)]
+#[strum(serialize_all = "snake_case")]
+pub enum MouseButton {
+	#[default]
+	Left,
+	Right,
+	Middle,
+}
+
+impl MouseButton {
+	/// The evdev code of the button, as reported in pointer button events.
+	pub fn code(self) -> u32 {
+		match self {
+			MouseButton::Left => 0x110,
+			MouseButton::Right => 0x111,
+			MouseButton::Middle => 0x112,
+		}
+	}
+}
+
+#[derive(Debug, Clone, SmartDefault, Config)]
+pub struct Focus {
+	/// Focuses the window under the pointer as it moves, otherwise focus changes on clicks.
+	#[default(true)]
+	pub follow_mouse: bool,
+	/// Moves the pointer to windows focused from the keyboard.
+	#[default(true)]
+	pub warp_pointer: bool,
+}
+
+#[derive(Debug, Clone, SmartDefault, Config)]
+pub struct Animations {
+	#[default(true)]
+	pub enable: bool,
+}
+
+#[derive(Debug, Clone, Config)]
+pub struct Keybinding {
+	#[config(flat)]
+	pub keys: Vec<String>,
+	#[config(flat)]
+	pub action: LuaFunction,
+}
+
+#[derive(Debug, Clone, Config)]
+pub struct Rule {
+	#[config(flat)]
+	pub triggers: Vec<Trigger>,
+	#[config(flat)]
+	pub action: LuaFunction,
+}
+
+#[derive(Debug, Clone, Config)]
+pub struct Trigger {
+	#[config(flat)]
+	pub event: String,
+	#[config(flat)]
+	pub class_name: Option<String>,
+	#[config(flat)]
+	pub workspace: Option<i32>,
+}
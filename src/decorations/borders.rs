@@ -1,7 +1,10 @@
 // Copyright 2023 the Strata authors
 // SPDX-License-Identifier: GPL-3.0-or-later
 
-use crate::decorations::BorderShader;
+use crate::{
+	config::Border,
+	decorations::BorderShader,
+};
 use smithay::{
 	backend::renderer::{
 		element::Element,
@@ -32,7 +35,59 @@ use std::{
 const ROUNDED_BORDER_FRAG: &str = include_str!("shaders/rounded_corners.frag");
 const BORDER_FRAG: &str = include_str!("shaders/borders.frag");
 
-struct BorderShaderElements(RefCell<HashMap<Window, PixelShaderElement>>);
+struct BorderShaderElements(RefCell<HashMap<Window, BorderElement>>);
+
+struct BorderElement {
+	element: PixelShaderElement,
+	uniforms: BorderUniforms,
+}
+
+/// Everything the border shader is parameterized with, compared between frames to find out
+/// whether the cached element is still up to date.
+#[derive(Clone, Copy, PartialEq)]
+struct BorderUniforms {
+	start_color: [f32; 3],
+	end_color: [f32; 3],
+	thickness: f32,
+	gradient_direction: [f32; 2],
+	/// `None` selects the square shader.
+	radius: Option<f32>,
+}
+
+impl BorderUniforms {
+	fn new(border: &Border, focused: bool) -> Self {
+		let thickness = border.width as f32;
+		let (start_color, end_color) = if focused {
+			(border.active, border.gradient.active)
+		} else {
+			(border.inactive, border.gradient.inactive)
+		};
+		let angle = (border.gradient.angle as f32).to_radians();
+
+		BorderUniforms {
+			start_color: start_color.0,
+			end_color: end_color.unwrap_or(start_color).0,
+			thickness,
+			gradient_direction: [angle.cos(), angle.sin()],
+			radius: (border.radius > 0.0).then_some(border.radius as f32 + thickness + 2.0),
+		}
+	}
+
+	fn to_vec(self) -> Vec<Uniform<'static>> {
+		let mut uniforms = vec![
+			Uniform::new("startColor", self.start_color),
+			Uniform::new("endColor", self.end_color),
+			Uniform::new("thickness", self.thickness),
+			Uniform::new("halfThickness", self.thickness * 0.5),
+			Uniform::new("gradientDirection", self.gradient_direction),
+		];
+		if let Some(radius) = self.radius {
+			uniforms.push(Uniform::new("radius", radius));
+		}
+
+		uniforms
+	}
+}
 
 impl BorderShader {
 	pub fn init(renderer: &mut GlowRenderer) {
@@ -79,9 +134,11 @@ impl BorderShader {
 		renderer: &mut GlowRenderer,
 		window: &Window,
 		loc: Point<i32, Logical>,
+		border: &Border,
+		focused: bool,
 	) -> PixelShaderElement {
-		// let thickness: f32 = CONFIG.read().decorations.border.width as f32;
-		let thickness = 2.0;
+		let uniforms = BorderUniforms::new(border, focused);
+		let thickness = uniforms.thickness;
 		let thickness_loc = (thickness as i32, thickness as i32);
 		let thickness_size = ((thickness * 2.0) as i32, (thickness * 2.0) as i32);
 		let geo = Rectangle::from_loc_and_size(
@@ -95,59 +152,39 @@ impl BorderShader {
 			.expect("Border Shader not initialized")
 			.0
 			.borrow_mut();
-		if let Some(elem) = elements.get_mut(window) {
-			if elem.geometry(1.0.into()).to_logical(1) != geo {
-				elem.resize(geo, None);
+
+		// switching between the rounded and square shader needs a new element, everything else
+		// is updated in place. Both `resize` and `update_uniforms` bump the element's commit, so
+		// the damage tracker redraws exactly the borders that changed.
+		match elements.get_mut(window) {
+			Some(cached) if cached.uniforms.radius.is_some() == uniforms.radius.is_some() => {
+				if cached.element.geometry(1.0.into()).to_logical(1) != geo {
+					cached.element.resize(geo, None);
+				}
+				if cached.uniforms != uniforms {
+					cached.element.update_uniforms(uniforms.to_vec());
+					cached.uniforms = uniforms;
+				}
+				cached.element.clone()
 			}
-			elem.clone()
-		} else {
-			let angle = 45 as f32 * std::f32::consts::PI;
-			let gradient_direction = [angle.cos(), angle.sin()];
-			// let elem = if CONFIG.read().decorations.border.radius > 0.0 {
-			let elem = if 5.0 > 0.0 {
-				PixelShaderElement::new(
-					Self::get(renderer).rounded.clone(),
+			_ => {
+				let program = if uniforms.radius.is_some() {
+					Self::get(renderer).rounded.clone()
+				} else {
+					Self::get(renderer).default.clone()
+				};
+				let element = PixelShaderElement::new(
+					program,
 					geo,
 					None,
 					1.0,
-					vec![
-						Uniform::new("startColor", [0.880, 1.0, 1.0]),
-						Uniform::new(
-							"endColor",
-							Some([0.580, 0.921, 0.921]).unwrap_or([0.880, 1.0, 1.0]),
-						),
-						Uniform::new("thickness", thickness),
-						Uniform::new("halfThickness", thickness * 0.5),
-						Uniform::new(
-							"radius",
-							// CONFIG.read().decorations.border.radius as f32 + thickness + 2.0,
-							5.0 + thickness + 2.0,
-						),
-						Uniform::new("gradientDirection", gradient_direction),
-					],
+					uniforms.to_vec(),
 					smithay::backend::renderer::element::Kind::Unspecified,
-				)
-			} else {
-				PixelShaderElement::new(
-					Self::get(renderer).default.clone(),
-					geo,
-					None,
-					1.0,
-					vec![
-						Uniform::new("startColor", [0.880, 1.0, 1.0]),
-						Uniform::new(
-							"endColor",
-							Some([0.580, 0.921, 0.921]).unwrap_or([0.880, 1.0, 1.0]),
-						),
-						Uniform::new("thickness", thickness),
-						Uniform::new("halfThickness", thickness * 0.5),
-						Uniform::new("gradientDirection", gradient_direction),
-					],
-					smithay::backend::renderer::element::Kind::Unspecified,
-				)
-			};
-			elements.insert(window.clone(), elem.clone());
-			elem
+				);
+				elements
+					.insert(window.clone(), BorderElement { element: element.clone(), uniforms });
+				element
+			}
 		}
 	}
 	pub fn cleanup(renderer: &mut GlowRenderer) {
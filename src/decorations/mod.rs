@@ -2,12 +2,18 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 mod borders;
+mod tab_bar;
+
+pub use tab_bar::TabBar;
 
 use smithay::{
 	backend::renderer::{
 		element::{
+			memory::MemoryRenderBufferRenderElement,
+			solid::SolidColorRenderElement,
 			surface::WaylandSurfaceRenderElement,
 			texture::TextureRenderElement,
+			utils::CropRenderElement,
 			Element,
 			Id,
 			RenderElement,
@@ -51,7 +57,11 @@ where
 {
 	Texture(TextureRenderElement<<R as Renderer>::TextureId>),
 	Surface(WaylandSurfaceRenderElement<R>),
+	/// Windows that only partly fit on the output, e.g. in a scrolled layout.
+	Cropped(CropRenderElement<WaylandSurfaceRenderElement<R>>),
 	Shader(PixelShaderElement),
+	Solid(SolidColorRenderElement),
+	TabBar(MemoryRenderBufferRenderElement<R>),
 }
 
 impl<R> Element for CustomRenderElements<R>
@@ -64,7 +74,10 @@ where
 		match self {
 			CustomRenderElements::Texture(elem) => elem.id(),
 			CustomRenderElements::Surface(elem) => elem.id(),
+			CustomRenderElements::Cropped(elem) => elem.id(),
 			CustomRenderElements::Shader(elem) => elem.id(),
+			CustomRenderElements::Solid(elem) => elem.id(),
+			CustomRenderElements::TabBar(elem) => elem.id(),
 		}
 	}
 
@@ -72,7 +85,10 @@ where
 		match self {
 			CustomRenderElements::Texture(elem) => elem.current_commit(),
 			CustomRenderElements::Surface(elem) => elem.current_commit(),
+			CustomRenderElements::Cropped(elem) => elem.current_commit(),
 			CustomRenderElements::Shader(elem) => elem.current_commit(),
+			CustomRenderElements::Solid(elem) => elem.current_commit(),
+			CustomRenderElements::TabBar(elem) => elem.current_commit(),
 		}
 	}
 
@@ -80,7 +96,10 @@ where
 		match self {
 			CustomRenderElements::Texture(elem) => elem.src(),
 			CustomRenderElements::Surface(elem) => elem.src(),
+			CustomRenderElements::Cropped(elem) => elem.src(),
 			CustomRenderElements::Shader(elem) => elem.src(),
+			CustomRenderElements::Solid(elem) => elem.src(),
+			CustomRenderElements::TabBar(elem) => elem.src(),
 		}
 	}
 
@@ -88,7 +107,10 @@ where
 		match self {
 			CustomRenderElements::Texture(elem) => elem.geometry(scale),
 			CustomRenderElements::Surface(elem) => elem.geometry(scale),
+			CustomRenderElements::Cropped(elem) => elem.geometry(scale),
 			CustomRenderElements::Shader(elem) => elem.geometry(scale),
+			CustomRenderElements::Solid(elem) => elem.geometry(scale),
+			CustomRenderElements::TabBar(elem) => elem.geometry(scale),
 		}
 	}
 
@@ -96,7 +118,10 @@ where
 		match self {
 			CustomRenderElements::Texture(elem) => elem.location(scale),
 			CustomRenderElements::Surface(elem) => elem.location(scale),
+			CustomRenderElements::Cropped(elem) => elem.location(scale),
 			CustomRenderElements::Shader(elem) => elem.location(scale),
+			CustomRenderElements::Solid(elem) => elem.location(scale),
+			CustomRenderElements::TabBar(elem) => elem.location(scale),
 		}
 	}
 
@@ -104,7 +129,10 @@ where
 		match self {
 			CustomRenderElements::Texture(elem) => elem.transform(),
 			CustomRenderElements::Surface(elem) => elem.transform(),
+			CustomRenderElements::Cropped(elem) => elem.transform(),
 			CustomRenderElements::Shader(elem) => elem.transform(),
+			CustomRenderElements::Solid(elem) => elem.transform(),
+			CustomRenderElements::TabBar(elem) => elem.transform(),
 		}
 	}
 
@@ -116,7 +144,10 @@ where
 		match self {
 			CustomRenderElements::Texture(elem) => elem.damage_since(scale, commit),
 			CustomRenderElements::Surface(elem) => elem.damage_since(scale, commit),
+			CustomRenderElements::Cropped(elem) => elem.damage_since(scale, commit),
 			CustomRenderElements::Shader(elem) => elem.damage_since(scale, commit),
+			CustomRenderElements::Solid(elem) => elem.damage_since(scale, commit),
+			CustomRenderElements::TabBar(elem) => elem.damage_since(scale, commit),
 		}
 	}
 
@@ -124,7 +155,10 @@ where
 		match self {
 			CustomRenderElements::Texture(elem) => elem.opaque_regions(scale),
 			CustomRenderElements::Surface(elem) => elem.opaque_regions(scale),
+			CustomRenderElements::Cropped(elem) => elem.opaque_regions(scale),
 			CustomRenderElements::Shader(elem) => elem.opaque_regions(scale),
+			CustomRenderElements::Solid(elem) => elem.opaque_regions(scale),
+			CustomRenderElements::TabBar(elem) => elem.opaque_regions(scale),
 		}
 	}
 }
@@ -144,10 +178,17 @@ impl<'a, 'b> RenderElement<GlMultiRenderer<'a, 'b>>
 				RenderElement::<GlMultiRenderer>::draw(elem, frame, src, dst, damage)
 			}
 			CustomRenderElements::Surface(elem) => elem.draw(frame, src, dst, damage),
+			CustomRenderElements::Cropped(elem) => elem.draw(frame, src, dst, damage),
 			CustomRenderElements::Shader(elem) => {
 				RenderElement::<GlowRenderer>::draw(elem, frame.as_mut(), src, dst, damage)
 					.map_err(MultiError::Render)
 			}
+			CustomRenderElements::Solid(elem) => {
+				RenderElement::<GlMultiRenderer>::draw(elem, frame, src, dst, damage)
+			}
+			CustomRenderElements::TabBar(elem) => {
+				RenderElement::<GlMultiRenderer>::draw(elem, frame, src, dst, damage)
+			}
 		}
 	}
 
@@ -158,7 +199,10 @@ impl<'a, 'b> RenderElement<GlMultiRenderer<'a, 'b>>
 		match self {
 			CustomRenderElements::Texture(elem) => elem.underlying_storage(renderer),
 			CustomRenderElements::Surface(elem) => elem.underlying_storage(renderer),
+			CustomRenderElements::Cropped(elem) => elem.underlying_storage(renderer),
 			CustomRenderElements::Shader(elem) => elem.underlying_storage(renderer.as_mut()),
+			CustomRenderElements::Solid(elem) => elem.underlying_storage(renderer),
+			CustomRenderElements::TabBar(elem) => elem.underlying_storage(renderer),
 		}
 	}
 }
@@ -176,9 +220,16 @@ impl RenderElement<GlowRenderer> for CustomRenderElements<GlowRenderer> {
 				RenderElement::<GlowRenderer>::draw(elem, frame, src, dst, damage)
 			}
 			CustomRenderElements::Surface(elem) => elem.draw(frame, src, dst, damage),
+			CustomRenderElements::Cropped(elem) => elem.draw(frame, src, dst, damage),
 			CustomRenderElements::Shader(elem) => {
 				RenderElement::<GlowRenderer>::draw(elem, frame, src, dst, damage)
 			}
+			CustomRenderElements::Solid(elem) => {
+				RenderElement::<GlowRenderer>::draw(elem, frame, src, dst, damage)
+			}
+			CustomRenderElements::TabBar(elem) => {
+				RenderElement::<GlowRenderer>::draw(elem, frame, src, dst, damage)
+			}
 		}
 	}
 }
@@ -209,6 +260,24 @@ where
 	}
 }
 
+impl<R> From<SolidColorRenderElement> for CustomRenderElements<R>
+where
+	R: Renderer,
+{
+	fn from(value: SolidColorRenderElement) -> Self {
+		CustomRenderElements::Solid(value)
+	}
+}
+
+impl<R> From<MemoryRenderBufferRenderElement<R>> for CustomRenderElements<R>
+where
+	R: Renderer,
+{
+	fn from(value: MemoryRenderBufferRenderElement<R>) -> Self {
+		CustomRenderElements::TabBar(value)
+	}
+}
+
 pub trait AsGlowRenderer
 where
 	Self: Renderer,
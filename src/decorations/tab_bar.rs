@@ -0,0 +1,190 @@
+// Copyright 2023 the Strata authors
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::cell::RefCell;
+
+use cosmic_text::{
+	Attrs,
+	Buffer as TextBuffer,
+	Color as TextColor,
+	FontSystem,
+	Metrics,
+	Shaping,
+	SwashCache,
+	Wrap,
+};
+use smithay::{
+	backend::{
+		allocator::Fourcc,
+		renderer::{
+			element::{
+				memory::{
+					MemoryRenderBuffer,
+					MemoryRenderBufferRenderElement,
+				},
+				Kind,
+			},
+			ImportMem,
+			Renderer,
+		},
+	},
+	utils::{
+		Logical,
+		Rectangle,
+		Size,
+		Transform,
+	},
+};
+
+use crate::{
+	config::{
+		Border,
+		Color,
+	},
+	decorations::AsGlowRenderer,
+};
+
+/// Titles are this much of the tab height.
+const FONT_SCALE: f32 = 0.55;
+/// Space between the edges of a tab and its title.
+const PADDING: usize = 8;
+
+/// Fonts are loaded once per renderer, the first time a tab bar is drawn.
+struct TabBarFonts(RefCell<(FontSystem, SwashCache)>);
+
+/// Everything a tab bar shows, compared between frames so it's only drawn again when it
+/// changed.
+#[derive(Clone, PartialEq)]
+struct TabBarContent {
+	size: Size<i32, Logical>,
+	titles: Vec<String>,
+	active: usize,
+	active_color: Color,
+	inactive_color: Color,
+}
+
+/// The tabs of a workspace, one per window with its title, the active one in the active
+/// border color.
+#[derive(Default)]
+pub struct TabBar {
+	cached: Option<(TabBarContent, MemoryRenderBuffer)>,
+}
+
+impl TabBar {
+	pub fn element<R>(
+		&mut self,
+		renderer: &mut R,
+		area: Rectangle<i32, Logical>,
+		titles: Vec<String>,
+		active: usize,
+		border: &Border,
+	) -> Option<MemoryRenderBufferRenderElement<R>>
+	where
+		R: Renderer + ImportMem + AsGlowRenderer,
+		<R as Renderer>::TextureId: 'static,
+	{
+		if area.size.w <= 0 || area.size.h <= 0 {
+			return None;
+		}
+		let content = TabBarContent {
+			size: area.size,
+			titles,
+			active,
+			active_color: border.active,
+			inactive_color: border.inactive,
+		};
+
+		if self.cached.as_ref().map(|(cached, _)| cached) != Some(&content) {
+			let user_data = renderer.glow_renderer().egl_context().user_data();
+			user_data.insert_if_missing(|| {
+				TabBarFonts(RefCell::new((FontSystem::new(), SwashCache::new())))
+			});
+			let fonts = &mut user_data.get::<TabBarFonts>().unwrap().0.borrow_mut();
+			let pixels = draw(&mut fonts.0, &mut fonts.1, &content);
+			let buffer = MemoryRenderBuffer::from_slice(
+				&pixels,
+				Fourcc::Argb8888,
+				(content.size.w, content.size.h),
+				1,
+				Transform::Normal,
+				None,
+			);
+			self.cached = Some((content, buffer));
+		}
+
+		let (_, buffer) = self.cached.as_ref()?;
+		MemoryRenderBufferRenderElement::from_buffer(
+			renderer,
+			area.loc.to_physical(1).to_f64(),
+			buffer,
+			None,
+			None,
+			None,
+			Kind::Unspecified,
+		)
+		.ok()
+	}
+}
+
+/// Draws the tabs side by side, as ARGB pixels.
+fn draw(font_system: &mut FontSystem, cache: &mut SwashCache, content: &TabBarContent) -> Vec<u8> {
+	let (width, height) = (content.size.w as usize, content.size.h as usize);
+	let mut pixels = vec![0; width * height * 4];
+	let count = content.titles.len().max(1);
+	let metrics = Metrics::new(height as f32 * FONT_SCALE, height as f32);
+
+	for (i, title) in content.titles.iter().enumerate() {
+		let (start, end) = (width * i / count, width * (i + 1) / count);
+		let background =
+			if i == content.active { content.active_color } else { content.inactive_color };
+		let background = background.0.map(|c| (c * 255.0).round() as u8);
+		for y in 0..height {
+			for x in start..end {
+				put_pixel(&mut pixels, width, x, y, background);
+			}
+		}
+
+		// titles are cut at the end of their tab
+		let text_width = end.saturating_sub(start + PADDING * 2);
+		let mut text = TextBuffer::new(font_system, metrics);
+		text.set_wrap(font_system, Wrap::None);
+		text.set_size(font_system, text_width as f32, height as f32);
+		text.set_text(font_system, title, Attrs::new(), Shaping::Advanced);
+		text.shape_until_scroll(font_system);
+		text.draw(font_system, cache, text_color(background), |x, y, w, h, color| {
+			let alpha = color.a() as u32;
+			if alpha == 0 {
+				return;
+			}
+			for y in y.max(0) as usize..(y.max(0) as usize + h as usize).min(height) {
+				for x in x.max(0) as usize..(x.max(0) as usize + w as usize).min(text_width) {
+					let blend = |fg: u8, bg: u8| {
+						((fg as u32 * alpha + bg as u32 * (255 - alpha)) / 255) as u8
+					};
+					let bg = background;
+					let rgb =
+						[blend(color.r(), bg[0]), blend(color.g(), bg[1]), blend(color.b(), bg[2])];
+					put_pixel(&mut pixels, width, start + PADDING + x, y, rgb);
+				}
+			}
+		});
+	}
+
+	pixels
+}
+
+fn put_pixel(pixels: &mut [u8], width: usize, x: usize, y: usize, [r, g, b]: [u8; 3]) {
+	let i = (y * width + x) * 4;
+	// Argb8888 is stored little endian
+	pixels[i..i + 4].copy_from_slice(&[b, g, r, 0xff]);
+}
+
+/// Black or white, whichever is easier to read on `background`.
+fn text_color([r, g, b]: [u8; 3]) -> TextColor {
+	let luminance = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+	if luminance > 128.0 {
+		TextColor::rgb(0x00, 0x00, 0x00)
+	} else {
+		TextColor::rgb(0xff, 0xff, 0xff)
+	}
+}
@@ -89,14 +89,21 @@ impl CompositorHandler for StrataComp {
 			while let Some(parent) = get_parent(&root) {
 				root = parent;
 			}
-			if let Some(window) =
-				self.workspaces.all_windows().find(|w| w.toplevel().wl_surface() == &root)
-			{
+			let window = self
+				.workspaces
+				.all_windows()
+				.find(|w| w.toplevel().wl_surface() == &root)
+				.map(|w| w.clone());
+			if let Some(window) = window {
 				window.on_commit();
+				if let Some(workspace) = self.workspaces.workspace_from_window(&window) {
+					workspace.floating_commit(&window);
+				}
 			}
 		};
 		self.popup_manager.commit(surface);
 		handle_commit(&self.workspaces, surface, &self.popup_manager);
+		self.schedule_render();
 	}
 }
 
@@ -207,7 +214,13 @@ impl WlrLayerShellHandler for StrataComp {
 		let output = output
 			.as_ref()
 			.and_then(Output::from_resource)
-			.unwrap_or_else(|| self.workspaces.current().outputs().next().unwrap().clone());
+			.filter(|output| self.workspaces.outputs().any(|o| o == output))
+			.or_else(|| self.workspaces.current_output().cloned());
+		let Some(output) = output else {
+			// there's nowhere to show the surface
+			surface.send_close();
+			return;
+		};
 		let mut map = layer_map_for_output(&output);
 		let layer_surface = LayerSurface::new(surface, namespace);
 		map.map_layer(&layer_surface).unwrap();
@@ -226,10 +239,11 @@ impl WlrLayerShellHandler for StrataComp {
 		}) {
 			map.unmap_layer(&layer);
 		}
-		self.set_input_focus_auto();
+		self.restore_focus();
 		for workspace in self.workspaces.iter() {
 			refresh_geometry(workspace);
 		}
+		self.schedule_render();
 	}
 }
 
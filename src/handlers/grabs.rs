@@ -0,0 +1,406 @@
+// Copyright 2023 the Strata authors
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use bitflags::bitflags;
+use smithay::{
+	desktop::Window,
+	input::{
+		pointer::{
+			AxisFrame,
+			ButtonEvent,
+			Focus,
+			GestureHoldBeginEvent,
+			GestureHoldEndEvent,
+			GesturePinchBeginEvent,
+			GesturePinchEndEvent,
+			GesturePinchUpdateEvent,
+			GestureSwipeBeginEvent,
+			GestureSwipeEndEvent,
+			GestureSwipeUpdateEvent,
+			GrabStartData as PointerGrabStartData,
+			MotionEvent,
+			PointerGrab,
+			PointerInnerHandle,
+			RelativeMotionEvent,
+		},
+		Seat,
+	},
+	reexports::{
+		wayland_protocols::xdg::shell::server::xdg_toplevel,
+		wayland_server::{
+			protocol::wl_surface::WlSurface,
+			Resource,
+		},
+	},
+	utils::{
+		Logical,
+		Point,
+		Rectangle,
+		Serial,
+	},
+	wayland::seat::WaylandFocus,
+};
+
+use crate::{
+	handlers::input::ModFlags,
+	state::StrataComp,
+	workspaces::FocusTarget,
+};
+
+/// Floating windows can't be resized below this size.
+const MIN_SIZE: i32 = 32;
+
+bitflags! {
+	#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+	pub struct ResizeEdge: u32 {
+		const TOP = 1;
+		const BOTTOM = 2;
+		const LEFT = 4;
+		const RIGHT = 8;
+	}
+}
+
+impl From<xdg_toplevel::ResizeEdge> for ResizeEdge {
+	fn from(edge: xdg_toplevel::ResizeEdge) -> Self {
+		ResizeEdge::from_bits_truncate(edge as u32)
+	}
+}
+
+/// Events the grabs don't care about go to the client as usual.
+macro_rules! forward_pointer_events {
+	() => {
+		fn relative_motion(
+			&mut self,
+			data: &mut StrataComp,
+			handle: &mut PointerInnerHandle<'_, StrataComp>,
+			_focus: Option<(FocusTarget, Point<i32, Logical>)>,
+			event: &RelativeMotionEvent,
+		) {
+			handle.relative_motion(data, None, event);
+		}
+
+		fn axis(
+			&mut self,
+			data: &mut StrataComp,
+			handle: &mut PointerInnerHandle<'_, StrataComp>,
+			details: AxisFrame,
+		) {
+			handle.axis(data, details);
+		}
+
+		fn frame(
+			&mut self,
+			data: &mut StrataComp,
+			handle: &mut PointerInnerHandle<'_, StrataComp>,
+		) {
+			handle.frame(data);
+		}
+
+		fn gesture_swipe_begin(
+			&mut self,
+			data: &mut StrataComp,
+			handle: &mut PointerInnerHandle<'_, StrataComp>,
+			event: &GestureSwipeBeginEvent,
+		) {
+			handle.gesture_swipe_begin(data, event);
+		}
+
+		fn gesture_swipe_update(
+			&mut self,
+			data: &mut StrataComp,
+			handle: &mut PointerInnerHandle<'_, StrataComp>,
+			event: &GestureSwipeUpdateEvent,
+		) {
+			handle.gesture_swipe_update(data, event);
+		}
+
+		fn gesture_swipe_end(
+			&mut self,
+			data: &mut StrataComp,
+			handle: &mut PointerInnerHandle<'_, StrataComp>,
+			event: &GestureSwipeEndEvent,
+		) {
+			handle.gesture_swipe_end(data, event);
+		}
+
+		fn gesture_pinch_begin(
+			&mut self,
+			data: &mut StrataComp,
+			handle: &mut PointerInnerHandle<'_, StrataComp>,
+			event: &GesturePinchBeginEvent,
+		) {
+			handle.gesture_pinch_begin(data, event);
+		}
+
+		fn gesture_pinch_update(
+			&mut self,
+			data: &mut StrataComp,
+			handle: &mut PointerInnerHandle<'_, StrataComp>,
+			event: &GesturePinchUpdateEvent,
+		) {
+			handle.gesture_pinch_update(data, event);
+		}
+
+		fn gesture_pinch_end(
+			&mut self,
+			data: &mut StrataComp,
+			handle: &mut PointerInnerHandle<'_, StrataComp>,
+			event: &GesturePinchEndEvent,
+		) {
+			handle.gesture_pinch_end(data, event);
+		}
+
+		fn gesture_hold_begin(
+			&mut self,
+			data: &mut StrataComp,
+			handle: &mut PointerInnerHandle<'_, StrataComp>,
+			event: &GestureHoldBeginEvent,
+		) {
+			handle.gesture_hold_begin(data, event);
+		}
+
+		fn gesture_hold_end(
+			&mut self,
+			data: &mut StrataComp,
+			handle: &mut PointerInnerHandle<'_, StrataComp>,
+			event: &GestureHoldEndEvent,
+		) {
+			handle.gesture_hold_end(data, event);
+		}
+
+		fn start_data(&self) -> &PointerGrabStartData<StrataComp> {
+			&self.start_data
+		}
+	};
+}
+
+/// Ends the grab once every button is released. Grabs started with the modifier never sent
+/// the press to the client, so they don't send it the release either.
+fn release_button(
+	start_data: &PointerGrabStartData<StrataComp>,
+	data: &mut StrataComp,
+	handle: &mut PointerInnerHandle<'_, StrataComp>,
+	event: &ButtonEvent,
+) -> bool {
+	if start_data.focus.is_some() {
+		handle.button(data, event);
+	}
+	if handle.current_pressed().is_empty() {
+		handle.unset_grab(data, event.serial, event.time, true);
+		return true;
+	}
+	false
+}
+
+pub struct MoveGrab {
+	start_data: PointerGrabStartData<StrataComp>,
+	window: Window,
+	initial_loc: Point<i32, Logical>,
+}
+
+impl PointerGrab<StrataComp> for MoveGrab {
+	fn motion(
+		&mut self,
+		data: &mut StrataComp,
+		handle: &mut PointerInnerHandle<'_, StrataComp>,
+		_focus: Option<(FocusTarget, Point<i32, Logical>)>,
+		event: &MotionEvent,
+	) {
+		// no client gets pointer focus while a window is dragged
+		handle.motion(data, None, event);
+
+		let delta: Point<i32, Logical> = (event.location - self.start_data.location).to_i32_round();
+		if let Some(workspace) = data.workspaces.workspace_from_window(&self.window) {
+			workspace.move_floating(&self.window, self.initial_loc + delta);
+		}
+	}
+
+	fn button(
+		&mut self,
+		data: &mut StrataComp,
+		handle: &mut PointerInnerHandle<'_, StrataComp>,
+		event: &ButtonEvent,
+	) {
+		release_button(&self.start_data, data, handle, event);
+	}
+
+	forward_pointer_events!();
+}
+
+pub struct ResizeGrab {
+	start_data: PointerGrabStartData<StrataComp>,
+	window: Window,
+	edges: ResizeEdge,
+	initial_rec: Rectangle<i32, Logical>,
+	/// Tiled windows are resized step by step, as their splits clamp the ratios.
+	last_location: Point<f64, Logical>,
+}
+
+impl PointerGrab<StrataComp> for ResizeGrab {
+	fn motion(
+		&mut self,
+		data: &mut StrataComp,
+		handle: &mut PointerInnerHandle<'_, StrataComp>,
+		_focus: Option<(FocusTarget, Point<i32, Logical>)>,
+		event: &MotionEvent,
+	) {
+		handle.motion(data, None, event);
+
+		let Some(workspace) = data.workspaces.workspace_from_window(&self.window) else {
+			return;
+		};
+		if workspace.is_floating(&self.window) {
+			let delta: Point<i32, Logical> =
+				(event.location - self.start_data.location).to_i32_round();
+			let initial = self.initial_rec;
+			let mut rec = initial;
+			if self.edges.contains(ResizeEdge::LEFT) {
+				rec.size.w = (initial.size.w - delta.x).max(MIN_SIZE);
+				rec.loc.x = initial.loc.x + initial.size.w - rec.size.w;
+			} else if self.edges.contains(ResizeEdge::RIGHT) {
+				rec.size.w = (initial.size.w + delta.x).max(MIN_SIZE);
+			}
+			if self.edges.contains(ResizeEdge::TOP) {
+				rec.size.h = (initial.size.h - delta.y).max(MIN_SIZE);
+				rec.loc.y = initial.loc.y + initial.size.h - rec.size.h;
+			} else if self.edges.contains(ResizeEdge::BOTTOM) {
+				rec.size.h = (initial.size.h + delta.y).max(MIN_SIZE);
+			}
+			workspace.resize_floating(&self.window, rec);
+		} else {
+			workspace.resize_tiled(&self.window, self.edges, event.location - self.last_location);
+		}
+		self.last_location = event.location;
+	}
+
+	fn button(
+		&mut self,
+		data: &mut StrataComp,
+		handle: &mut PointerInnerHandle<'_, StrataComp>,
+		event: &ButtonEvent,
+	) {
+		if release_button(&self.start_data, data, handle, event) {
+			if let Some(workspace) = data.workspaces.workspace_from_window(&self.window) {
+				workspace.finish_resize(&self.window);
+			}
+		}
+	}
+
+	forward_pointer_events!();
+}
+
+/// Checks that a move or resize request comes from a client holding an implicit grab, i.e.
+/// one of its surfaces got the button press with that serial.
+pub fn check_grab(
+	seat: &Seat<StrataComp>,
+	surface: &WlSurface,
+	serial: Serial,
+) -> Option<PointerGrabStartData<StrataComp>> {
+	let pointer = seat.get_pointer()?;
+	if !pointer.has_grab(serial) {
+		return None;
+	}
+
+	let start_data = pointer.grab_start_data()?;
+	let (focus, _) = start_data.focus.as_ref()?;
+	if !focus.same_client_as(&surface.id()) {
+		return None;
+	}
+
+	Some(start_data)
+}
+
+impl StrataComp {
+	/// Starts moving a floating window. Returns whether the grab started.
+	pub fn start_move_grab(
+		&mut self,
+		window: Window,
+		start_data: PointerGrabStartData<StrataComp>,
+		serial: Serial,
+	) -> bool {
+		let Some(workspace) = self.workspaces.workspace_from_window(&window) else {
+			return false;
+		};
+		if !workspace.is_floating(&window) {
+			return false;
+		}
+		let Some(rec) = workspace.window_geometry(&window) else {
+			return false;
+		};
+		let Some(pointer) = self.seat.get_pointer() else {
+			return false;
+		};
+		workspace.raise_window(&window);
+
+		let grab = MoveGrab { start_data, window, initial_loc: rec.loc };
+		pointer.set_grab(self, grab, serial, Focus::Clear);
+		true
+	}
+
+	/// Starts resizing a window from `edges`. Returns whether the grab started.
+	pub fn start_resize_grab(
+		&mut self,
+		window: Window,
+		edges: ResizeEdge,
+		start_data: PointerGrabStartData<StrataComp>,
+		serial: Serial,
+	) -> bool {
+		let Some(initial_rec) = self
+			.workspaces
+			.workspace_from_window(&window)
+			.and_then(|ws| ws.window_geometry(&window))
+		else {
+			return false;
+		};
+		let Some(pointer) = self.seat.get_pointer() else {
+			return false;
+		};
+
+		let grab = ResizeGrab {
+			last_location: start_data.location,
+			start_data,
+			window,
+			edges,
+			initial_rec,
+		};
+		pointer.set_grab(self, grab, serial, Focus::Clear);
+		true
+	}
+
+	/// Starts a move or resize grab if `button` is bound to one in the config and its modifier
+	/// is held. Resizing drags the edges closest to the pointer. Returns whether the button
+	/// was taken by the grab.
+	pub fn start_mouse_grab(&mut self, window: Window, button: u32, serial: Serial) -> bool {
+		let mouse = &self.config.options.mouse;
+		let (move_button, resize_button) = (mouse.move_button.code(), mouse.resize_button.code());
+		if !ModFlags::from_name(&mouse.modifier).is_some_and(|m| self.mods.flags.contains(m)) {
+			return false;
+		}
+		let Some(pointer) = self.seat.get_pointer() else {
+			return false;
+		};
+
+		let location = pointer.current_location();
+		let start_data = PointerGrabStartData { focus: None, button, location };
+		if button == move_button {
+			self.start_move_grab(window, start_data, serial)
+		} else if button == resize_button {
+			let Some(rec) = self
+				.workspaces
+				.workspace_from_window(&window)
+				.and_then(|ws| ws.window_geometry(&window))
+			else {
+				return false;
+			};
+			let (center_x, center_y) = (rec.loc.x + rec.size.w / 2, rec.loc.y + rec.size.h / 2);
+			let mut edges = ResizeEdge::empty();
+			edges |=
+				if location.x < center_x as f64 { ResizeEdge::LEFT } else { ResizeEdge::RIGHT };
+			edges |=
+				if location.y < center_y as f64 { ResizeEdge::TOP } else { ResizeEdge::BOTTOM };
+			self.start_resize_grab(window, edges, start_data, serial)
+		} else {
+			false
+		}
+	}
+}
@@ -4,7 +4,11 @@
 use crate::{
 	enum_table,
 	state::StrataComp,
-	workspaces::FocusTarget,
+	workspaces::{
+		output_geometry,
+		Direction,
+		FocusTarget,
+	},
 };
 use bitflags::bitflags;
 use smithay::{
@@ -12,12 +16,14 @@ use smithay::{
 		AbsolutePositionEvent,
 		Axis,
 		AxisSource,
+		ButtonState,
 		Event,
 		InputBackend,
 		PointerAxisEvent,
 		PointerButtonEvent,
 		PointerMotionEvent,
 	},
+	desktop::Window,
 	input::{
 		keyboard::{
 			Keysym,
@@ -30,7 +36,11 @@ use smithay::{
 			RelativeMotionEvent,
 		},
 	},
-	utils::SERIAL_COUNTER,
+	utils::{
+		Logical,
+		Point,
+		SERIAL_COUNTER,
+	},
 };
 
 #[derive(Debug)]
@@ -2630,13 +2640,140 @@ pub struct KeyPattern {
 	pub key: Key,
 }
 
+impl KeyPattern {
+	/// Parses a binding written as names, e.g. `{ "Super_L", "Shift_L", "Return" }`.
+	/// Every name but the last is a modifier.
+	pub fn from_names<S: AsRef<str>>(names: &[S]) -> anyhow::Result<Self> {
+		let (key, mods) =
+			names.split_last().ok_or_else(|| anyhow::anyhow!("key binding without keys"))?;
+
+		let mut flags = ModFlags::empty();
+		for name in mods {
+			let name = name.as_ref();
+			flags |= ModFlags::from_name(name)
+				.ok_or_else(|| anyhow::anyhow!("invalid Mod key: {}", name))?;
+		}
+
+		// digits aren't valid identifiers, so the key table names them `_0` to `_9`
+		let name = key.as_ref();
+		let key = Key::from_name(name)
+			.or_else(|| Key::from_name(&format!("_{}", name)))
+			.ok_or_else(|| anyhow::anyhow!("invalid key: {}", name))?;
+
+		Ok(KeyPattern { mods: flags, key: key.into() })
+	}
+}
+
 impl StrataComp {
 	pub fn set_input_focus(&mut self, target: FocusTarget) {
+		if let FocusTarget::Window(window) = &target {
+			if let Some(workspace) = self.workspaces.workspace_from_window(window) {
+				workspace.record_focus(window);
+			}
+		}
 		let keyboard = self.seat.get_keyboard().unwrap();
 		let serial = SERIAL_COUNTER.next_serial();
 		keyboard.set_focus(self, Some(target), serial);
 	}
 
+	/// The window that window actions apply to: the last one focused on the current
+	/// workspace, even while a layer surface (e.g. a launcher) has the keyboard.
+	pub fn focused_window(&self) -> Option<Window> {
+		self.workspaces.current().focused_window()
+	}
+
+	/// Gives the keyboard back to the window last focused on the current workspace. Without
+	/// one, it goes to what is under the pointer if focus follows the mouse.
+	pub fn restore_focus(&mut self) {
+		if let Some(window) = self.focused_window() {
+			self.set_input_focus(FocusTarget::Window(window));
+		} else if self.config.options.focus.follow_mouse {
+			self.set_input_focus_auto();
+		} else {
+			let keyboard = self.seat.get_keyboard().unwrap();
+			keyboard.set_focus(self, None, SERIAL_COUNTER.next_serial());
+		}
+	}
+
+	/// Focuses a window of the current workspace from the keyboard, moving the pointer to it
+	/// if `focus.warp_pointer` is set.
+	pub fn focus_window(&mut self, window: Window) {
+		let workspace = self.workspaces.current_mut();
+		let rec = workspace.window_geometry(&window);
+		workspace.raise_window(&window);
+		self.set_input_focus(FocusTarget::Window(window));
+
+		if let Some(rec) = rec.filter(|_| self.config.options.focus.warp_pointer) {
+			let center = (rec.loc.x + rec.size.w / 2, rec.loc.y + rec.size.h / 2);
+			self.warp_pointer(Point::from(center).to_f64());
+		}
+	}
+
+	pub fn warp_pointer(&mut self, location: Point<f64, Logical>) {
+		let Some(ptr) = self.seat.get_pointer() else {
+			return;
+		};
+		let serial = SERIAL_COUNTER.next_serial();
+		let time = self.start_time.elapsed().as_millis() as u32;
+		let under = self.surface_at(location);
+		ptr.motion(self, under, &MotionEvent { location, serial, time });
+	}
+
+	pub fn focus_direction(&mut self, direction: Direction) {
+		let workspace = self.workspaces.current();
+		let target = match self.focused_window() {
+			Some(window) => workspace.window_in_direction(&window, direction),
+			None => workspace.focus_order().into_iter().next(),
+		};
+		if let Some(window) = target {
+			self.focus_window(window);
+		}
+	}
+
+	pub fn swap_direction(&mut self, direction: Direction) {
+		let Some(window) = self.focused_window() else {
+			return;
+		};
+		if self.workspaces.current_mut().swap_direction(&window, direction).is_some() {
+			// the pointer goes along with the window
+			self.focus_window(window);
+		}
+	}
+
+	pub fn move_direction(&mut self, direction: Direction) {
+		let Some(window) = self.focused_window() else {
+			return;
+		};
+		if self.workspaces.current_mut().move_direction(&window, direction).is_some() {
+			self.focus_window(window);
+		}
+	}
+
+	pub fn promote_window(&mut self) {
+		let Some(window) = self.focused_window() else {
+			return;
+		};
+		self.workspaces.current_mut().promote_window(&window);
+		self.focus_window(window);
+	}
+
+	/// Focuses the window `offset` places after the focused one in the workspace's focus
+	/// order, wrapping around at the ends.
+	pub fn focus_offset(&mut self, offset: isize) {
+		let windows = self.workspaces.current().focus_order();
+		if windows.is_empty() {
+			return;
+		}
+
+		let focused = self.focused_window().and_then(|f| windows.iter().position(|w| *w == f));
+		let index = match focused {
+			Some(i) => (i as isize + offset).rem_euclid(windows.len() as isize) as usize,
+			None if offset < 0 => windows.len() - 1,
+			None => 0,
+		};
+		self.focus_window(windows[index].clone());
+	}
+
 	pub fn set_input_focus_auto(&mut self) {
 		let under = self.surface_under();
 		if let Some(d) = under {
@@ -2651,10 +2788,22 @@ impl StrataComp {
 		let serial = SERIAL_COUNTER.next_serial();
 		let delta = (event.delta_x(), event.delta_y()).into();
 
-		self.set_input_focus_auto();
+		// focus stays on the window being moved or resized
+		let grabbed = self.seat.get_pointer().is_some_and(|ptr| ptr.is_grabbed());
+		if self.config.options.focus.follow_mouse && !grabbed {
+			self.set_input_focus_auto();
+		}
 
 		if let Some(ptr) = self.seat.get_pointer() {
-			let location = self.workspaces.current().clamp_coords(ptr.current_location() + delta);
+			let location = self
+				.workspaces
+				.clamp_coords(ptr.current_location() + delta, ptr.current_location());
+			// the workspace under the pointer becomes the current one as it crosses outputs
+			if let Some(output) = self.workspaces.output_at(location).cloned() {
+				if self.workspaces.current_output() != Some(&output) {
+					self.workspaces.focus_output(&output);
+				}
+			}
 
 			let under = self.surface_under();
 
@@ -2684,14 +2833,16 @@ impl StrataComp {
 	) -> anyhow::Result<()> {
 		let serial = SERIAL_COUNTER.next_serial();
 
-		let curr_workspace = self.workspaces.current();
-		let output = curr_workspace.outputs().next().unwrap();
-		let output_geo = curr_workspace.output_geometry(output).unwrap();
+		let Some(output_geo) = self.workspaces.current_output().and_then(output_geometry) else {
+			return Ok(());
+		};
 		let pos = event.position_transformed(output_geo.size) + output_geo.loc.to_f64();
 
-		let location = self.workspaces.current().clamp_coords(pos);
+		let location = self.workspaces.clamp_coords(pos, pos);
 
-		self.set_input_focus_auto();
+		if self.config.options.focus.follow_mouse {
+			self.set_input_focus_auto();
+		}
 
 		let under = self.surface_under();
 		if let Some(ptr) = self.seat.get_pointer() {
@@ -2709,6 +2860,14 @@ impl StrataComp {
 		let button = event.button_code();
 		let button_state = event.state();
 		self.set_input_focus_auto();
+		if button_state == ButtonState::Pressed {
+			if let Some((FocusTarget::Window(window), _)) = self.surface_under() {
+				self.workspaces.current_mut().raise_window(&window);
+				if self.start_mouse_grab(window, button, serial) {
+					return Ok(());
+				}
+			}
+		}
 		if let Some(ptr) = self.seat.get_pointer() {
 			ptr.button(
 				self,
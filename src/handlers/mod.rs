@@ -3,5 +3,6 @@
 
 pub mod compositor;
 pub mod focus;
+pub mod grabs;
 pub mod input;
 pub mod xdg_shell;
@@ -18,12 +18,17 @@ use smithay::{
 		Window,
 		WindowSurfaceType,
 	},
+	input::Seat,
 	reexports::{
 		wayland_protocols::xdg::{
 			decoration::zv1::server::zxdg_toplevel_decoration_v1::Mode,
-			shell::server::xdg_toplevel::State as ToplevelState,
+			shell::server::xdg_toplevel::{
+				ResizeEdge,
+				State as ToplevelState,
+			},
 		},
 		wayland_server::protocol::{
+			wl_output::WlOutput,
 			wl_seat::WlSeat,
 			wl_surface::WlSurface,
 		},
@@ -48,6 +53,7 @@ use smithay::{
 };
 
 use crate::{
+	handlers::grabs::check_grab,
 	state::StrataComp,
 	workspaces::{
 		StrataWindow,
@@ -62,16 +68,18 @@ impl XdgShellHandler for StrataComp {
 
 	fn new_toplevel(&mut self, surface: ToplevelSurface) {
 		let window = Window::new(surface);
-		self.workspaces.current_mut().add_window(Rc::new(RefCell::new(StrataWindow {
-			smithay_window: window.clone(),
-			rec: window.geometry(),
-		})));
+		self.workspaces.current_mut().add_window(Rc::new(RefCell::new(StrataWindow::new(window))));
 	}
 	fn toplevel_destroyed(&mut self, surface: ToplevelSurface) {
 		let window =
 			self.workspaces.all_windows().find(|w| w.toplevel() == &surface).unwrap().clone();
 
+		let focused = self.focused_window().as_ref() == Some(&window);
 		self.workspaces.workspace_from_window(&window).unwrap().remove_window(&window);
+		if focused {
+			self.restore_focus();
+		}
+		self.schedule_render();
 	}
 	fn new_popup(&mut self, surface: PopupSurface, positioner: PositionerState) {
 		surface.with_pending_state(|state| {
@@ -84,6 +92,72 @@ impl XdgShellHandler for StrataComp {
 
 	fn grab(&mut self, _surface: PopupSurface, _seat: WlSeat, _serial: Serial) {}
 
+	fn move_request(&mut self, surface: ToplevelSurface, seat: WlSeat, serial: Serial) {
+		let Some(seat) = Seat::<StrataComp>::from_resource(&seat) else {
+			return;
+		};
+		let Some(start_data) = check_grab(&seat, surface.wl_surface(), serial) else {
+			return;
+		};
+		let Some(window) = self.workspaces.find_window(&surface) else {
+			return;
+		};
+
+		self.start_move_grab(window, start_data, serial);
+	}
+
+	fn resize_request(
+		&mut self,
+		surface: ToplevelSurface,
+		seat: WlSeat,
+		serial: Serial,
+		edges: ResizeEdge,
+	) {
+		let Some(seat) = Seat::<StrataComp>::from_resource(&seat) else {
+			return;
+		};
+		let Some(start_data) = check_grab(&seat, surface.wl_surface(), serial) else {
+			return;
+		};
+		let Some(window) = self.workspaces.find_window(&surface) else {
+			return;
+		};
+
+		self.start_resize_grab(window, edges.into(), start_data, serial);
+	}
+
+	fn fullscreen_request(&mut self, surface: ToplevelSurface, _output: Option<WlOutput>) {
+		if let Some(window) = self.workspaces.find_window(&surface) {
+			if let Some(workspace) = self.workspaces.workspace_from_window(&window) {
+				workspace.set_fullscreen(&window, true);
+			}
+		}
+	}
+
+	fn unfullscreen_request(&mut self, surface: ToplevelSurface) {
+		if let Some(window) = self.workspaces.find_window(&surface) {
+			if let Some(workspace) = self.workspaces.workspace_from_window(&window) {
+				workspace.set_fullscreen(&window, false);
+			}
+		}
+	}
+
+	fn maximize_request(&mut self, surface: ToplevelSurface) {
+		if let Some(window) = self.workspaces.find_window(&surface) {
+			if let Some(workspace) = self.workspaces.workspace_from_window(&window) {
+				workspace.set_maximized(&window, true);
+			}
+		}
+	}
+
+	fn unmaximize_request(&mut self, surface: ToplevelSurface) {
+		if let Some(window) = self.workspaces.find_window(&surface) {
+			if let Some(workspace) = self.workspaces.workspace_from_window(&window) {
+				workspace.set_maximized(&window, false);
+			}
+		}
+	}
+
 	fn reposition_request(
 		&mut self,
 		_surface: PopupSurface,
@@ -96,30 +170,61 @@ impl XdgShellHandler for StrataComp {
 
 delegate_xdg_shell!(StrataComp);
 
+pub fn initial_configure_sent(toplevel: &ToplevelSurface) -> bool {
+	with_states(toplevel.wl_surface(), |states| {
+		states
+			.data_map
+			.get::<Mutex<XdgToplevelSurfaceRoleAttributes>>()
+			.unwrap()
+			.lock()
+			.unwrap()
+			.initial_configure_sent
+	})
+}
+
+/// The title and app id the client gave `toplevel`.
+pub fn title_and_app_id(toplevel: &ToplevelSurface) -> (Option<String>, Option<String>) {
+	with_states(toplevel.wl_surface(), |states| {
+		let attributes = states
+			.data_map
+			.get::<Mutex<XdgToplevelSurfaceRoleAttributes>>()
+			.unwrap()
+			.lock()
+			.unwrap();
+		(attributes.title.clone(), attributes.app_id.clone())
+	})
+}
+
+/// Tells the client whether it is tiled, so it can drop its shadows and rounded corners.
+pub fn set_tiled_states(toplevel: &ToplevelSurface, tiled: bool) {
+	toplevel.with_pending_state(|state| {
+		for tiled_state in [
+			ToplevelState::TiledLeft,
+			ToplevelState::TiledRight,
+			ToplevelState::TiledTop,
+			ToplevelState::TiledBottom,
+		] {
+			if tiled {
+				state.states.set(tiled_state);
+			} else {
+				state.states.unset(tiled_state);
+			}
+		}
+	});
+}
+
 pub fn handle_commit(workspaces: &Workspaces, surface: &WlSurface, popup_manager: &PopupManager) {
 	if let Some(window) = workspaces.all_windows().find(|w| w.toplevel().wl_surface() == surface) {
-		let initial_configure_sent = with_states(surface, |states| {
-			states
-				.data_map
-				.get::<Mutex<XdgToplevelSurfaceRoleAttributes>>()
-				.unwrap()
-				.lock()
-				.unwrap()
-				.initial_configure_sent
-		});
-		if !initial_configure_sent {
-			let toplevel = window.toplevel();
-			toplevel.with_pending_state(|state| {
-				state.states.set(ToplevelState::TiledLeft);
-				state.states.set(ToplevelState::TiledRight);
-				state.states.set(ToplevelState::TiledTop);
-				state.states.set(ToplevelState::TiledBottom);
-			});
+		let toplevel = window.toplevel();
+		if !initial_configure_sent(toplevel) {
+			// windows made floating before their first commit already have their state set
+			let floating = workspaces.workspaces.iter().any(|ws| ws.is_floating(&window));
+			set_tiled_states(toplevel, !floating);
 			toplevel.send_configure();
 		}
 	}
 
-	if let Some(output) = workspaces.current().outputs().find(|o| {
+	if let Some(output) = workspaces.outputs().find(|o| {
 		let map = layer_map_for_output(o);
 		map.layer_for_surface(surface, WindowSurfaceType::TOPLEVEL).is_some()
 	}) {
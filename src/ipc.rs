@@ -0,0 +1,145 @@
+// Copyright 2023 the Strata authors
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+	ffi::OsStr,
+	fs,
+	io::{
+		ErrorKind,
+		Read,
+		Write,
+	},
+	os::unix::net::{
+		UnixListener,
+		UnixStream,
+	},
+	path::PathBuf,
+};
+
+use log::{
+	info,
+	warn,
+};
+use smithay::reexports::calloop::{
+	generic::Generic,
+	Interest,
+	LoopHandle,
+	Mode,
+	PostAction,
+};
+
+use crate::state::StrataState;
+
+/// Clients that send more than this without a newline are dropped.
+const MAX_LINE: usize = 4096;
+
+/// Binds `$XDG_RUNTIME_DIR/strata-<wayland socket>.sock` and exports its path as
+/// `STRATA_SOCKET`. Clients send one command per line and get one line back.
+pub fn init_ipc_listener(
+	loop_handle: &LoopHandle<'static, StrataState>,
+	wayland_socket: &OsStr,
+) -> anyhow::Result<PathBuf> {
+	let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR")
+		.map(PathBuf::from)
+		.ok_or_else(|| anyhow::anyhow!("XDG_RUNTIME_DIR is not set"))?;
+	let path = runtime_dir.join(format!("strata-{}.sock", wayland_socket.to_string_lossy()));
+
+	// a stale socket from a crashed session would make bind fail
+	let _ = fs::remove_file(&path);
+	let listener = UnixListener::bind(&path)?;
+	listener.set_nonblocking(true)?;
+
+	loop_handle
+		.insert_source(Generic::new(listener, Interest::READ, Mode::Level), |_, listener, state| {
+			while let Ok((stream, _)) = listener.as_ref().accept() {
+				if let Err(err) = state.add_ipc_client(stream) {
+					warn!("IPC client error: {}", err);
+				}
+			}
+
+			Ok(PostAction::Continue)
+		})
+		.map_err(|err| err.error)?;
+
+	std::env::set_var("STRATA_SOCKET", &path);
+	info!("IPC socket listening on {}", path.display());
+
+	Ok(path)
+}
+
+impl StrataState {
+	/// Watches `stream` for commands alongside the other event sources, so that a client that
+	/// is slow to send them never holds up the compositor.
+	fn add_ipc_client(&mut self, stream: UnixStream) -> anyhow::Result<()> {
+		stream.set_nonblocking(true)?;
+		let mut buffer = Vec::new();
+
+		self.comp
+			.borrow()
+			.loop_handle
+			.insert_source(
+				Generic::new(stream, Interest::READ, Mode::Level),
+				move |_, stream, state| match state.read_ipc_client(stream.as_ref(), &mut buffer) {
+					Ok(true) => Ok(PostAction::Continue),
+					Ok(false) => Ok(PostAction::Remove),
+					Err(err) => {
+						warn!("IPC client error: {}", err);
+						Ok(PostAction::Remove)
+					}
+				},
+			)
+			.map_err(|err| err.error)?;
+
+		Ok(())
+	}
+
+	/// Reads what `stream` has sent so far into `buffer` and answers each complete line.
+	/// Returns `false` once the client is done sending.
+	fn read_ipc_client(
+		&mut self,
+		mut stream: &UnixStream,
+		buffer: &mut Vec<u8>,
+	) -> anyhow::Result<bool> {
+		let mut chunk = [0; 1024];
+		let open = match stream.read(&mut chunk) {
+			Ok(0) => false,
+			Ok(read) => {
+				buffer.extend_from_slice(&chunk[..read]);
+				true
+			}
+			Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::Interrupted) => {
+				return Ok(true);
+			}
+			Err(err) => return Err(err.into()),
+		};
+
+		while let Some(end) = buffer.iter().position(|&byte| byte == b'\n') {
+			let line: Vec<u8> = buffer.drain(..=end).collect();
+			self.answer_ipc(stream, &line)?;
+		}
+		if !open && !buffer.is_empty() {
+			// the last command may come without a newline
+			let line = std::mem::take(buffer);
+			self.answer_ipc(stream, &line)?;
+		}
+		if buffer.len() > MAX_LINE {
+			anyhow::bail!("command longer than {} bytes", MAX_LINE);
+		}
+
+		Ok(open)
+	}
+
+	fn answer_ipc(&mut self, mut stream: &UnixStream, line: &[u8]) -> anyhow::Result<()> {
+		let response = match String::from_utf8_lossy(line).trim() {
+			"" => return Ok(()),
+			"reload" => match self.reload_config() {
+				Ok(()) => "ok".to_string(),
+				Err(err) => format!("error: {:#}", err),
+			},
+			unknown => format!("error: unknown command: {}", unknown),
+		};
+
+		writeln!(stream, "{}", response)?;
+		Ok(())
+	}
+}
@@ -0,0 +1,449 @@
+// Copyright 2023 the Strata authors
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+	cell::RefCell,
+	rc::Rc,
+};
+
+use log::{
+	error,
+	warn,
+};
+use piccolo as lua;
+use smithay::{
+	desktop::Window,
+	utils::{
+		Logical,
+		Rectangle,
+	},
+};
+use strata_core::{
+	FromLua,
+	ToLua,
+};
+use strata_derive::Config;
+
+use super::Layout;
+use crate::{
+	config::LayoutKind,
+	handlers::xdg_shell::title_and_app_id,
+	state::StrataState,
+	tiling::refresh_geometry,
+	workspaces::{
+		Direction,
+		Dwindle,
+		HorizontalOrVertical,
+		StrataWindow,
+	},
+};
+
+/// A layout written in Lua. The Lua function gets the area to fill, the windows and a table
+/// for its own state, and returns a rectangle for each window.
+///
+/// Lua can't run while a workspace is being laid out, so `arrange` only records that the
+/// function has to run for the new windows or area. Until it did, and whenever it returns
+/// something unusable, the windows are arranged by `fallback`.
+pub struct CustomLayout {
+	name: String,
+	/// Keeps the order of the windows, which the Lua function gets them in.
+	fallback: Dwindle,
+	state: Option<lua::StashedTable>,
+	arrangement: RefCell<Arrangement>,
+}
+
+/// The input of the last arrangement and what the Lua function made of it.
+#[derive(Default)]
+struct Arrangement {
+	area: Rectangle<i32, Logical>,
+	inner: i32,
+	windows: Vec<Window>,
+	/// `None` until the Lua function ran, or if what it returned was invalid.
+	recs: Option<Vec<Rectangle<i32, Logical>>>,
+	/// Whether the Lua function still has to run for this input.
+	pending: bool,
+}
+
+/// A call the Lua function of a layout is waiting for.
+pub struct LayoutRequest {
+	area: Rectangle<i32, Logical>,
+	inner: i32,
+	windows: Vec<Rc<RefCell<StrataWindow>>>,
+}
+
+/// A rectangle as Lua layouts get and return them.
+#[derive(Debug, Clone, Copy, PartialEq, Config)]
+pub struct LuaRect {
+	pub x: i32,
+	pub y: i32,
+	pub width: i32,
+	pub height: i32,
+}
+
+/// What Lua layouts know about a window.
+#[derive(Debug, Clone, Config)]
+pub struct LuaWindow {
+	pub title: Option<String>,
+	pub app_id: Option<String>,
+}
+
+impl From<Rectangle<i32, Logical>> for LuaRect {
+	fn from(rec: Rectangle<i32, Logical>) -> Self {
+		LuaRect { x: rec.loc.x, y: rec.loc.y, width: rec.size.w, height: rec.size.h }
+	}
+}
+
+impl From<LuaRect> for Rectangle<i32, Logical> {
+	fn from(rec: LuaRect) -> Self {
+		Rectangle::from_loc_and_size((rec.x, rec.y), (rec.width, rec.height))
+	}
+}
+
+impl CustomLayout {
+	pub fn new(name: String) -> Self {
+		CustomLayout {
+			name,
+			fallback: Dwindle::new(),
+			state: None,
+			arrangement: RefCell::new(Arrangement::default()),
+		}
+	}
+
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	/// Runs the Lua function again, e.g. because it was (re)registered.
+	pub fn invalidate(&mut self) {
+		let arrangement = self.arrangement.get_mut();
+		arrangement.recs = None;
+		arrangement.pending = true;
+	}
+
+	pub fn take_request(&mut self) -> Option<LayoutRequest> {
+		let arrangement = self.arrangement.get_mut();
+		if !std::mem::take(&mut arrangement.pending) {
+			return None;
+		}
+		Some(LayoutRequest {
+			area: arrangement.area,
+			inner: arrangement.inner,
+			windows: self.fallback.windows(),
+		})
+	}
+
+	/// Keeps `recs` as the arrangement of `request`, unless the windows or the area changed
+	/// in the meantime.
+	fn finish_request(
+		&mut self,
+		request: &LayoutRequest,
+		recs: Option<Vec<Rectangle<i32, Logical>>>,
+	) {
+		let arrangement = self.arrangement.get_mut();
+		let windows = request.windows.iter().map(|w| w.borrow().smithay_window.clone());
+		if arrangement.area == request.area
+			&& arrangement.inner == request.inner
+			&& arrangement.windows.iter().cloned().eq(windows)
+		{
+			arrangement.recs = recs;
+		}
+	}
+}
+
+/// How long a Lua layout may run before it is taken for stuck: `LAYOUT_STEPS` steps of
+/// `FUEL_PER_STEP` fuel, roughly a VM instruction each.
+const LAYOUT_STEPS: usize = 256;
+const FUEL_PER_STEP: i32 = 4096;
+
+/// Runs `ex` to completion like `Lua::execute`, but gives up once it used up its fuel, so a
+/// layout that never returns can't hang the compositor.
+fn execute_with_fuel(lua_vm: &mut lua::Lua, ex: &lua::StashedExecutor) -> anyhow::Result<()> {
+	for _ in 0..LAYOUT_STEPS {
+		let mut fuel = lua::Fuel::with(FUEL_PER_STEP);
+		if lua_vm.enter(|ctx| ctx.fetch(ex).step(ctx, &mut fuel)) {
+			lua_vm.try_enter(|ctx| ctx.fetch(ex).take_result::<()>(ctx)?)?;
+			return Ok(());
+		}
+	}
+	anyhow::bail!("didn't return after {} instructions", LAYOUT_STEPS * FUEL_PER_STEP as usize)
+}
+
+/// Checks that a Lua layout returned one rectangle per window, each within `area`.
+fn validate(
+	recs: Vec<LuaRect>,
+	area: Rectangle<i32, Logical>,
+	count: usize,
+) -> anyhow::Result<Vec<Rectangle<i32, Logical>>> {
+	if recs.len() != count {
+		anyhow::bail!("returned {} rectangles for {} windows", recs.len(), count);
+	}
+	recs.into_iter()
+		.map(Rectangle::from)
+		.map(|rec| {
+			if rec.size.w <= 0 || rec.size.h <= 0 {
+				anyhow::bail!("returned an empty rectangle {:?}", rec);
+			}
+			if !area.contains_rect(rec) {
+				anyhow::bail!("returned {:?}, which is outside of {:?}", rec, area);
+			}
+			Ok(rec)
+		})
+		.collect()
+}
+
+impl Layout for CustomLayout {
+	fn kind(&self) -> LayoutKind {
+		LayoutKind::Custom(self.name.clone())
+	}
+
+	fn windows(&self) -> Vec<Rc<RefCell<StrataWindow>>> {
+		self.fallback.windows()
+	}
+
+	fn insert(&mut self, window: Rc<RefCell<StrataWindow>>) {
+		self.fallback.insert(window);
+	}
+
+	fn insert_beside(
+		&mut self,
+		window: Rc<RefCell<StrataWindow>>,
+		target: &Window,
+		direction: Direction,
+		ratio: f32,
+	) {
+		self.fallback.insert_beside(window, target, direction, ratio);
+	}
+
+	fn remove(&mut self, window: &Window) {
+		self.fallback.remove(window);
+	}
+
+	fn arrange(&self, area: Rectangle<i32, Logical>, inner: i32) -> Vec<Rectangle<i32, Logical>> {
+		let windows: Vec<_> =
+			self.fallback.windows().iter().map(|w| w.borrow().smithay_window.clone()).collect();
+		let mut arrangement = self.arrangement.borrow_mut();
+		if arrangement.area != area || arrangement.inner != inner || arrangement.windows != windows
+		{
+			*arrangement = Arrangement { area, inner, windows, recs: None, pending: true };
+		}
+
+		match &arrangement.recs {
+			Some(recs) => recs.clone(),
+			None => self.fallback.arrange(area, inner),
+		}
+	}
+
+	fn swap(&mut self, window: &Window, other: &Window) -> bool {
+		self.fallback.swap(window, other)
+	}
+
+	fn move_to(&mut self, window: &Window, target: &Window) -> bool {
+		self.fallback.move_to(window, target)
+	}
+
+	fn promote(&mut self, window: &Window) -> bool {
+		self.fallback.promote(window)
+	}
+
+	fn resize(
+		&mut self,
+		window: &Window,
+		orientation: HorizontalOrVertical,
+		window_is_first: bool,
+		delta: f64,
+	) -> bool {
+		self.fallback.resize(window, orientation, window_is_first, delta)
+	}
+
+	fn toggle_split(&mut self, window: &Window) -> bool {
+		self.fallback.toggle_split(window)
+	}
+
+	fn reset_ratios(&mut self) {
+		self.fallback.reset_ratios();
+	}
+
+	fn custom(&mut self) -> Option<&mut CustomLayout> {
+		Some(self)
+	}
+}
+
+impl StrataState {
+	/// Calls the Lua layouts whose windows or area changed since they last ran, and lays out
+	/// their workspaces with the result. Runs once the event loop is done dispatching, as Lua
+	/// isn't running then.
+	pub fn run_custom_layouts(&mut self) {
+		let requests: Vec<_> = {
+			let mut comp = self.comp.borrow_mut();
+			let comp = &mut *comp;
+			comp.workspaces
+				.iter()
+				.enumerate()
+				.filter_map(|(index, workspace)| {
+					let custom = workspace.layout.custom()?;
+					let request = custom.take_request()?;
+					let function = comp.config.layouts.get(custom.name()).cloned();
+					Some((
+						index,
+						custom.name().to_string(),
+						function,
+						custom.state.clone(),
+						request,
+					))
+				})
+				.collect()
+		};
+
+		for (index, name, function, state, request) in requests {
+			let (recs, state) = match function {
+				Some(function) => match self.call_layout(&function, state, &request) {
+					Ok((recs, state)) => (Some(recs), Some(state)),
+					Err(err) => {
+						error!("Layout {} failed, falling back to dwindle: {:#}", name, err);
+						(None, None)
+					}
+				},
+				None => {
+					warn!("No layout named {} is registered, falling back to dwindle", name);
+					(None, None)
+				}
+			};
+
+			let mut comp = self.comp.borrow_mut();
+			let Some(workspace) = comp.workspaces.workspaces.get_mut(index) else {
+				continue;
+			};
+			let Some(custom) = workspace.layout.custom().filter(|c| c.name() == name) else {
+				continue;
+			};
+			custom.finish_request(&request, recs);
+			if state.is_some() {
+				custom.state = state;
+			}
+			refresh_geometry(workspace);
+			comp.schedule_render();
+		}
+	}
+
+	fn call_layout(
+		&mut self,
+		function: &lua::StashedFunction,
+		state: Option<lua::StashedTable>,
+		request: &LayoutRequest,
+	) -> anyhow::Result<(Vec<Rectangle<i32, Logical>>, lua::StashedTable)> {
+		let windows: Vec<_> = request
+			.windows
+			.iter()
+			.map(|w| {
+				let (title, app_id) = title_and_app_id(w.borrow().smithay_window.toplevel());
+				LuaWindow { title, app_id }
+			})
+			.collect();
+
+		// the result comes back through `done`, as it only lives inside the Lua context
+		let result = Rc::new(RefCell::new(None));
+		let done_result = result.clone();
+		let (ex, state) = self.lua.try_enter(|ctx| {
+			let done = lua::Callback::from_fn(&ctx, move |ctx, _, mut stack| {
+				let recs = stack.consume::<lua::Value>(ctx)?;
+				*done_result.borrow_mut() = Some(Vec::<LuaRect>::from_lua(ctx, recs)?);
+				Ok(lua::CallbackReturn::Return)
+			});
+			let call = lua::Closure::load(
+				ctx,
+				Some("layout"),
+				&b"local f, done, area, windows, state = ...; done(f(area, windows, state))"[..],
+			)?;
+			let state = match &state {
+				Some(state) => ctx.fetch(state),
+				None => lua::Table::new(&ctx),
+			};
+			let args = (
+				ctx.fetch(function),
+				done,
+				LuaRect::from(request.area).to_lua(ctx),
+				windows.to_lua(ctx),
+				state,
+			);
+			Ok((ctx.stash(lua::Executor::start(ctx, call.into(), args)), ctx.stash(state)))
+		})?;
+		execute_with_fuel(&mut self.lua, &ex)?;
+
+		let recs = result.take().ok_or_else(|| anyhow::anyhow!("returned nothing"))?;
+		Ok((validate(recs, request.area, request.windows.len())?, state))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use piccolo as lua;
+	use smithay::utils::{
+		Logical,
+		Rectangle,
+	};
+
+	use super::{
+		execute_with_fuel,
+		validate,
+		LuaRect,
+	};
+
+	fn area() -> Rectangle<i32, Logical> {
+		Rectangle::from_loc_and_size((10, 20), (100, 50))
+	}
+
+	fn rect(x: i32, y: i32, width: i32, height: i32) -> LuaRect {
+		LuaRect { x, y, width, height }
+	}
+
+	#[test]
+	fn accepts_one_rect_per_window_within_the_area() {
+		let recs = vec![rect(10, 20, 50, 50), rect(60, 20, 50, 50)];
+		let validated = validate(recs.clone(), area(), 2).unwrap();
+		assert_eq!(validated, recs.into_iter().map(Rectangle::from).collect::<Vec<_>>());
+	}
+
+	#[test]
+	fn accepts_no_rects_without_windows() {
+		assert!(validate(Vec::new(), area(), 0).unwrap().is_empty());
+	}
+
+	#[test]
+	fn rejects_the_wrong_number_of_rects() {
+		assert!(validate(vec![rect(10, 20, 100, 50)], area(), 2).is_err());
+		assert!(validate(vec![rect(10, 20, 50, 50), rect(60, 20, 50, 50)], area(), 1).is_err());
+	}
+
+	#[test]
+	fn rejects_empty_rects() {
+		assert!(validate(vec![rect(10, 20, 0, 50)], area(), 1).is_err());
+		assert!(validate(vec![rect(10, 20, 100, -5)], area(), 1).is_err());
+	}
+
+	#[test]
+	fn rejects_rects_outside_the_area() {
+		assert!(validate(vec![rect(0, 20, 50, 50)], area(), 1).is_err());
+		assert!(validate(vec![rect(60, 20, 51, 50)], area(), 1).is_err());
+		assert!(validate(vec![rect(10, 21, 100, 50)], area(), 1).is_err());
+	}
+
+	fn run(source: &'static str) -> anyhow::Result<()> {
+		let mut lua_vm = lua::Lua::core();
+		let ex = lua_vm.try_enter(|ctx| {
+			let main = lua::Closure::load(ctx, Some("test"), source.as_bytes())?;
+			Ok(ctx.stash(lua::Executor::start(ctx, main.into(), ())))
+		})?;
+		execute_with_fuel(&mut lua_vm, &ex)
+	}
+
+	#[test]
+	fn runs_layouts_that_return() {
+		assert!(run("local n = 0; for i = 1, 1000 do n = n + i end").is_ok());
+		assert!(run("error('broken')").is_err());
+	}
+
+	#[test]
+	fn stops_layouts_that_never_return() {
+		assert!(run("while true do end").is_err());
+	}
+}
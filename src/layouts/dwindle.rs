@@ -6,66 +6,166 @@ use std::{
 	rc::Rc,
 };
 
-use smithay::desktop::Window;
+use smithay::{
+	desktop::Window,
+	utils::{
+		Logical,
+		Rectangle,
+	},
+};
 
-use crate::workspaces::{
-	Dwindle,
-	HorizontalOrVertical,
-	StrataWindow,
+use super::Layout;
+use crate::{
+	config::LayoutKind,
+	tiling::split_rec,
+	workspaces::{
+		Direction,
+		Dwindle,
+		HorizontalOrVertical,
+		StrataWindow,
+	},
 };
 
-impl Dwindle {
-	pub fn new() -> Self {
-		Dwindle::Empty
+/// Split ratios are kept in this range so neither side of a split collapses.
+pub const MIN_RATIO: f32 = 0.1;
+pub const MAX_RATIO: f32 = 0.9;
+
+impl<W: Clone> Dwindle<W> {
+	/// The windows of the tree, from left to right.
+	pub fn leaves(&self) -> Vec<W> {
+		match self {
+			Dwindle::Empty => Vec::new(),
+			Dwindle::Window(w) => vec![w.clone()],
+			Dwindle::Split { left, right, .. } => {
+				let mut windows = left.leaves();
+				windows.extend(right.leaves());
+				windows
+			}
+		}
+	}
+
+	fn find(&self, is: &impl Fn(&W) -> bool) -> bool {
+		match self {
+			Dwindle::Empty => false,
+			Dwindle::Window(w) => is(w),
+			Dwindle::Split { left, right, .. } => left.find(is) || right.find(is),
+		}
+	}
+
+	/// Puts `windows` into the leaves of this subtree, from left to right.
+	fn fill(&mut self, windows: &mut impl Iterator<Item = W>) {
+		match self {
+			Dwindle::Empty => {}
+			Dwindle::Window(w) => {
+				if let Some(window) = windows.next() {
+					*w = window;
+				}
+			}
+			Dwindle::Split { left, right, .. } => {
+				left.fill(windows);
+				right.fill(windows);
+			}
+		}
 	}
 
-	pub fn insert(
+	/// Replaces the leaf `is` matches with a split holding both it and `window`, with
+	/// `window` on its `direction` side. `ratio` is the share of the area `window` gets.
+	pub fn split_leaf(
 		&mut self,
-		window: Rc<RefCell<StrataWindow>>,
-		splitnew: HorizontalOrVertical,
-		rationew: f32,
+		is: &impl Fn(&W) -> bool,
+		window: W,
+		direction: Direction,
+		ratio: f32,
 	) {
+		match self {
+			Dwindle::Empty => {}
+			Dwindle::Window(w) => {
+				if !is(w) {
+					return;
+				}
+				let old = Box::new(std::mem::take(self));
+				let new = Box::new(Dwindle::Window(window));
+				let split = direction.orientation();
+				*self = if direction.is_first() {
+					Dwindle::Split { split, ratio, left: new, right: old }
+				} else {
+					Dwindle::Split { split, ratio: 1.0 - ratio, left: old, right: new }
+				};
+			}
+			Dwindle::Split { left, right, .. } => {
+				if left.find(is) {
+					left.split_leaf(is, window, direction, ratio);
+				} else {
+					right.split_leaf(is, window, direction, ratio);
+				}
+			}
+		}
+	}
+
+	/// Adds `window` at the end of the spine of the tree, splitting the last leaf along
+	/// `splitnew`.
+	pub fn insert_split(&mut self, window: W, splitnew: HorizontalOrVertical, rationew: f32) {
 		match self {
 			Dwindle::Empty => {
 				*self = Dwindle::Window(window);
 			}
-			Dwindle::Window(w) => {
+			Dwindle::Window(_) => {
 				*self = Dwindle::Split {
-					left: Box::new(Dwindle::Window(w.clone())),
+					left: Box::new(std::mem::take(self)),
 					right: Box::new(Dwindle::Window(window)),
 					split: splitnew,
 					ratio: rationew,
 				};
 			}
-			Dwindle::Split { left: _, right, split: _, ratio: _ } => {
-				right.insert(window, splitnew, rationew);
+			Dwindle::Split { right, .. } => {
+				right.insert_split(window, splitnew, rationew);
 			}
 		}
 	}
 
-	pub fn remove(&mut self, window: &Window) {
+	/// Takes the leaf `is` matches out of the tree. Its split goes with it, the other side
+	/// of the split takes its place, keeping its own splits and ratios.
+	pub fn remove_leaf(&mut self, is: &impl Fn(&W) -> bool) -> Option<W> {
 		match self {
-			Dwindle::Empty => {}
+			Dwindle::Empty => None,
 			Dwindle::Window(w) => {
-				if w.borrow().smithay_window == *window {
-					*self = Dwindle::Empty;
+				if !is(w) {
+					return None;
 				}
-			}
-			Dwindle::Split { left, right, split: _, ratio: _ } => {
-				if let Dwindle::Window(w) = left.as_ref() {
-					if w.borrow().smithay_window == *window {
-						*self = *right.clone();
-						return;
-					}
+				match std::mem::take(self) {
+					Dwindle::Window(w) => Some(w),
+					_ => None,
 				}
-				if let Dwindle::Window(w) = right.as_ref() {
-					if w.borrow().smithay_window == *window {
-						*self = *left.clone();
-						return;
-					}
+			}
+			Dwindle::Split { left, right, .. } => {
+				let removed = left.remove_leaf(is).or_else(|| right.remove_leaf(is))?;
+				let rest = match (left.as_mut(), right.as_mut()) {
+					(Dwindle::Empty, rest) | (rest, Dwindle::Empty) => Some(std::mem::take(rest)),
+					_ => None,
+				};
+				if let Some(rest) = rest {
+					*self = rest;
 				}
-				left.remove(window);
-				right.remove(window);
+				Some(removed)
+			}
+		}
+	}
+
+	/// Lays out this subtree in `area`, splitting it at every split of the tree, whichever
+	/// side the splits are on.
+	fn arrange_into(
+		&self,
+		area: Rectangle<i32, Logical>,
+		inner: i32,
+		recs: &mut Vec<Rectangle<i32, Logical>>,
+	) {
+		match self {
+			Dwindle::Empty => {}
+			Dwindle::Window(_) => recs.push(area),
+			Dwindle::Split { split, ratio, left, right } => {
+				let (first, second) = split_rec(area, *split, *ratio, inner);
+				left.arrange_into(first, inner, recs);
+				right.arrange_into(second, inner, recs);
 			}
 		}
 	}
@@ -87,8 +187,343 @@ impl Dwindle {
 	}
 }
 
-impl Default for Dwindle {
+impl Dwindle {
+	pub fn new() -> Self {
+		Dwindle::Empty
+	}
+
+	/// Rearranges the windows of the tree while keeping its splits and ratios. `reorder` gets
+	/// the windows from left to right and the position of `window` among them.
+	fn reorder(
+		&mut self,
+		window: &Window,
+		reorder: impl FnOnce(&mut Vec<Rc<RefCell<StrataWindow>>>, usize),
+	) -> bool {
+		let Some(index) = self.position(window) else {
+			return false;
+		};
+		let mut leaves = self.leaves();
+		reorder(&mut leaves, index);
+		self.fill(&mut leaves.into_iter());
+		true
+	}
+
+	fn position(&self, window: &Window) -> Option<usize> {
+		self.leaves().iter().position(|w| w.borrow().smithay_window == *window)
+	}
+
+	/// The area covered by the windows of this subtree, as of the last layout.
+	pub fn bounds(&self) -> Option<Rectangle<i32, Logical>> {
+		match self {
+			Dwindle::Empty => None,
+			Dwindle::Window(w) => Some(w.borrow().rec),
+			Dwindle::Split { left, right, .. } => match (left.bounds(), right.bounds()) {
+				(Some(left), Some(right)) => Some(left.merge(right)),
+				(left, right) => left.or(right),
+			},
+		}
+	}
+}
+
+/// Matches the leaf holding `window`.
+fn is(window: &Window) -> impl Fn(&Rc<RefCell<StrataWindow>>) -> bool + '_ {
+	move |w| w.borrow().smithay_window == *window
+}
+
+impl<W> Default for Dwindle<W> {
 	fn default() -> Self {
-		Self::new()
+		Dwindle::Empty
+	}
+}
+
+impl Layout for Dwindle {
+	fn kind(&self) -> LayoutKind {
+		LayoutKind::Dwindle
+	}
+
+	fn windows(&self) -> Vec<Rc<RefCell<StrataWindow>>> {
+		self.leaves()
+	}
+
+	fn contains(&self, window: &Window) -> bool {
+		self.find(&is(window))
+	}
+
+	fn insert(&mut self, window: Rc<RefCell<StrataWindow>>) {
+		self.insert_split(window, self.next_split(), 0.5);
+	}
+
+	fn insert_beside(
+		&mut self,
+		window: Rc<RefCell<StrataWindow>>,
+		target: &Window,
+		direction: Direction,
+		ratio: f32,
+	) {
+		if self.contains(target) {
+			self.split_leaf(&is(target), window, direction, ratio);
+		} else {
+			self.insert(window);
+		}
+	}
+
+	fn remove(&mut self, window: &Window) {
+		self.remove_leaf(&is(window));
+	}
+
+	fn arrange(&self, area: Rectangle<i32, Logical>, inner: i32) -> Vec<Rectangle<i32, Logical>> {
+		let mut recs = Vec::new();
+		self.arrange_into(area, inner, &mut recs);
+		recs
+	}
+
+	fn swap(&mut self, window: &Window, other: &Window) -> bool {
+		let Some(other) = self.position(other) else {
+			return false;
+		};
+		self.reorder(window, |leaves, index| leaves.swap(index, other))
+	}
+
+	/// Moves `window` to the node of `target`, shifting the windows in between by one.
+	fn move_to(&mut self, window: &Window, target: &Window) -> bool {
+		let Some(target) = self.position(target) else {
+			return false;
+		};
+		self.reorder(window, |leaves, index| {
+			let window = leaves.remove(index);
+			leaves.insert(target, window);
+		})
+	}
+
+	/// Moves `window` to the first leaf of the tree. The master window trades places with
+	/// the next one instead.
+	fn promote(&mut self, window: &Window) -> bool {
+		self.reorder(window, |leaves, index| {
+			if index == 0 {
+				if leaves.len() > 1 {
+					leaves.swap(0, 1);
+				}
+			} else {
+				let window = leaves.remove(index);
+				leaves.insert(0, window);
+			}
+		})
+	}
+
+	/// Changes the ratio of the innermost split along `orientation` that has `window` on its
+	/// first side with `window_is_first`, or on its second side without.
+	fn resize(
+		&mut self,
+		window: &Window,
+		orientation: HorizontalOrVertical,
+		window_is_first: bool,
+		delta: f64,
+	) -> bool {
+		let Dwindle::Split { split, ratio, left, right } = self else {
+			return false;
+		};
+		let in_left = left.contains(window);
+		if !in_left && !right.contains(window) {
+			return false;
+		}
+
+		let child = if in_left { left.as_mut() } else { right.as_mut() };
+		if child.resize(window, orientation, window_is_first, delta) {
+			return true;
+		}
+		if *split != orientation || in_left != window_is_first {
+			return false;
+		}
+
+		let Some(bounds) =
+			left.bounds().into_iter().chain(right.bounds()).reduce(|a, b| a.merge(b))
+		else {
+			return false;
+		};
+		let extent = match split {
+			HorizontalOrVertical::Horizontal => bounds.size.w,
+			HorizontalOrVertical::Vertical => bounds.size.h,
+		};
+		if extent > 0 {
+			*ratio = (*ratio + (delta / extent as f64) as f32).clamp(MIN_RATIO, MAX_RATIO);
+		}
+		true
+	}
+
+	/// Flips the orientation of the innermost split holding `window`.
+	fn toggle_split(&mut self, window: &Window) -> bool {
+		let Dwindle::Split { split, left, right, .. } = self else {
+			return false;
+		};
+		if left.toggle_split(window) || right.toggle_split(window) {
+			return true;
+		}
+		if !left.contains(window) && !right.contains(window) {
+			return false;
+		}
+
+		*split = match split {
+			HorizontalOrVertical::Horizontal => HorizontalOrVertical::Vertical,
+			HorizontalOrVertical::Vertical => HorizontalOrVertical::Horizontal,
+		};
+		true
+	}
+
+	/// Puts every split back to the middle.
+	fn reset_ratios(&mut self) {
+		if let Dwindle::Split { ratio, left, right, .. } = self {
+			*ratio = 0.5;
+			left.reset_ratios();
+			right.reset_ratios();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use proptest::prelude::*;
+	use smithay::utils::Rectangle;
+
+	use super::Dwindle;
+	use crate::{
+		layouts::testing::{
+			area,
+			inside,
+			overlap,
+		},
+		workspaces::Direction,
+	};
+
+	/// What a user can do to the tree. Indices pick one of the windows, wrapping around.
+	#[derive(Debug, Clone)]
+	enum Op {
+		Insert,
+		InsertBeside(usize, Direction, f32),
+		Remove(usize),
+	}
+
+	fn op() -> impl Strategy<Value = Op> {
+		let direction = prop_oneof![
+			Just(Direction::Left),
+			Just(Direction::Right),
+			Just(Direction::Up),
+			Just(Direction::Down),
+		];
+		prop_oneof![
+			Just(Op::Insert),
+			(any::<usize>(), direction, 0.1f32..0.9)
+				.prop_map(|(i, d, r)| Op::InsertBeside(i, d, r)),
+			any::<usize>().prop_map(Op::Remove),
+		]
+	}
+
+	/// Applies `ops` to an empty tree and to the list of windows it should hold, in order.
+	fn build(ops: &[Op]) -> (Dwindle<u32>, Vec<u32>) {
+		let mut tree = Dwindle::default();
+		let mut windows = Vec::new();
+		for (id, op) in (0..).zip(ops) {
+			match *op {
+				Op::Insert => {
+					tree.insert_split(id, tree.next_split(), 0.5);
+					windows.push(id);
+				}
+				Op::InsertBeside(i, direction, ratio) if !windows.is_empty() => {
+					let index = i % windows.len();
+					let target = windows[index];
+					tree.split_leaf(&|w| *w == target, id, direction, ratio);
+					let index = if direction.is_first() { index } else { index + 1 };
+					windows.insert(index, id);
+				}
+				Op::Remove(i) if !windows.is_empty() => {
+					let window = windows.remove(i % windows.len());
+					assert_eq!(tree.remove_leaf(&|w| *w == window), Some(window));
+				}
+				_ => {}
+			}
+		}
+		(tree, windows)
+	}
+
+	fn has_empty_subtree(tree: &Dwindle<u32>) -> bool {
+		match tree {
+			Dwindle::Split { left, right, .. } => {
+				matches!(**left, Dwindle::Empty)
+					|| matches!(**right, Dwindle::Empty)
+					|| has_empty_subtree(left)
+					|| has_empty_subtree(right)
+			}
+			_ => false,
+		}
+	}
+
+	proptest! {
+		#[test]
+		fn keeps_windows_in_order(ops in prop::collection::vec(op(), 0..64)) {
+			let (tree, windows) = build(&ops);
+			prop_assert_eq!(tree.leaves(), windows);
+			prop_assert!(!has_empty_subtree(&tree));
+		}
+
+		#[test]
+		fn removes_any_window(ops in prop::collection::vec(op(), 1..64), i in any::<usize>()) {
+			let (mut tree, mut windows) = build(&ops);
+			if windows.is_empty() {
+				return Ok(());
+			}
+			let window = windows.remove(i % windows.len());
+			prop_assert_eq!(tree.remove_leaf(&|w| *w == window), Some(window));
+			prop_assert_eq!(tree.remove_leaf(&|w| *w == window), None);
+			prop_assert_eq!(tree.leaves(), windows.clone());
+			prop_assert!(!has_empty_subtree(&tree));
+			prop_assert_eq!(windows.is_empty(), matches!(tree, Dwindle::Empty));
+		}
+
+		#[test]
+		fn covers_the_area(
+			ops in prop::collection::vec(op(), 0..64),
+			x in -2000..2000,
+			y in -2000..2000,
+			w in 1..4000,
+			h in 1..4000,
+		) {
+			let (tree, windows) = build(&ops);
+			let full = Rectangle::from_loc_and_size((x, y), (w, h));
+			let mut recs = Vec::new();
+			tree.arrange_into(full, 0, &mut recs);
+
+			prop_assert_eq!(recs.len(), windows.len());
+			for (i, rec) in recs.iter().enumerate() {
+				prop_assert!(rec.size.w >= 0 && rec.size.h >= 0, "{:?}", rec);
+				prop_assert!(inside(*rec, full), "{:?}", rec);
+				for other in &recs[i + 1..] {
+					prop_assert_eq!(overlap(*rec, *other), 0, "{:?} {:?}", rec, other);
+				}
+			}
+			if !windows.is_empty() {
+				prop_assert_eq!(recs.iter().copied().map(area).sum::<i64>(), area(full));
+			}
+		}
+
+		#[test]
+		fn leaves_gaps(
+			ops in prop::collection::vec(op(), 0..64),
+			inner in 0..50,
+			w in 1..4000,
+			h in 1..4000,
+		) {
+			let (tree, windows) = build(&ops);
+			let full = Rectangle::from_loc_and_size((0, 0), (w, h));
+			let mut recs = Vec::new();
+			tree.arrange_into(full, inner, &mut recs);
+
+			prop_assert_eq!(recs.len(), windows.len());
+			for (i, rec) in recs.iter().enumerate() {
+				prop_assert!(rec.size.w >= 0 && rec.size.h >= 0, "{:?}", rec);
+				prop_assert!(inside(*rec, full), "{:?}", rec);
+				for other in &recs[i + 1..] {
+					prop_assert_eq!(overlap(*rec, *other), 0, "{:?} {:?}", rec, other);
+				}
+			}
+		}
 	}
 }
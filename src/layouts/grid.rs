@@ -0,0 +1,160 @@
+// Copyright 2023 the Strata authors
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+	cell::RefCell,
+	rc::Rc,
+};
+
+use smithay::{
+	desktop::Window,
+	utils::{
+		Logical,
+		Rectangle,
+	},
+};
+
+use super::{
+	master_stack::stack,
+	Layout,
+};
+use crate::{
+	config::LayoutKind,
+	workspaces::{
+		Direction,
+		HorizontalOrVertical,
+		StrataWindow,
+	},
+};
+
+/// Puts the windows in rows of equal height, with as many columns as rows or one more. The
+/// windows of a last row that isn't full share its width.
+#[derive(Default)]
+pub struct Grid {
+	windows: WindowList,
+}
+
+impl Grid {
+	pub fn new() -> Self {
+		Grid::default()
+	}
+}
+
+/// The number of columns and rows of the squarest grid that fits `count` windows.
+fn dimensions(count: usize) -> (usize, usize) {
+	let mut columns = 1;
+	while columns * columns < count {
+		columns += 1;
+	}
+	(columns, count.div_ceil(columns))
+}
+
+/// The rectangles of `count` windows in a grid filling `area`.
+fn grid(count: usize, area: Rectangle<i32, Logical>, inner: i32) -> Vec<Rectangle<i32, Logical>> {
+	let (columns, rows) = dimensions(count);
+	let mut row_areas = Vec::with_capacity(rows);
+	stack(rows, area, HorizontalOrVertical::Vertical, inner, &mut row_areas);
+
+	let mut recs = Vec::with_capacity(count);
+	for (i, row) in row_areas.into_iter().enumerate() {
+		let in_row = columns.min(count - i * columns);
+		stack(in_row, row, HorizontalOrVertical::Horizontal, inner, &mut recs);
+	}
+	recs
+}
+
+impl Layout for Grid {
+	fn kind(&self) -> LayoutKind {
+		LayoutKind::Grid
+	}
+
+	fn windows(&self) -> Vec<Rc<RefCell<StrataWindow>>> {
+		self.windows.windows()
+	}
+
+	fn insert(&mut self, window: Rc<RefCell<StrataWindow>>) {
+		self.windows.push(window);
+	}
+
+	fn insert_beside(
+		&mut self,
+		window: Rc<RefCell<StrataWindow>>,
+		target: &Window,
+		direction: Direction,
+		_ratio: f32,
+	) {
+		self.windows.insert_beside(window, target, direction);
+	}
+
+	fn remove(&mut self, window: &Window) {
+		self.windows.remove(window);
+	}
+
+	fn arrange(&self, area: Rectangle<i32, Logical>, inner: i32) -> Vec<Rectangle<i32, Logical>> {
+		grid(self.windows.len(), area, inner)
+	}
+
+	fn swap(&mut self, window: &Window, other: &Window) -> bool {
+		self.windows.swap(window, other)
+	}
+
+	fn move_to(&mut self, window: &Window, target: &Window) -> bool {
+		self.windows.move_to(window, target)
+	}
+
+	/// Makes `window` the first one, at the top left.
+	fn promote(&mut self, window: &Window) -> bool {
+		self.windows.promote(window)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use smithay::utils::Rectangle;
+
+	use super::{
+		dimensions,
+		grid,
+	};
+	use crate::layouts::testing::{
+		area,
+		overlap,
+	};
+
+	#[test]
+	fn picks_the_squarest_grid() {
+		assert_eq!(dimensions(0), (1, 0));
+		assert_eq!(dimensions(1), (1, 1));
+		assert_eq!(dimensions(2), (2, 1));
+		assert_eq!(dimensions(3), (2, 2));
+		assert_eq!(dimensions(5), (3, 2));
+		assert_eq!(dimensions(10), (4, 3));
+	}
+
+	#[test]
+	fn fills_rows_and_shares_the_last_one() {
+		let full = Rectangle::from_loc_and_size((0, 0), (1200, 600));
+		for (count, row_lengths) in
+			[(1, vec![1]), (2, vec![2]), (3, vec![2, 1]), (5, vec![3, 2]), (10, vec![4, 4, 2])]
+		{
+			let recs = grid(count, full, 0);
+			assert_eq!(recs.len(), count);
+
+			let mut rows: Vec<(i32, usize)> = Vec::new();
+			for rec in &recs {
+				match rows.last_mut() {
+					Some((y, len)) if *y == rec.loc.y => *len += 1,
+					_ => rows.push((rec.loc.y, 1)),
+				}
+			}
+			assert_eq!(rows.into_iter().map(|(_, len)| len).collect::<Vec<_>>(), row_lengths);
+
+			for (i, rec) in recs.iter().enumerate() {
+				for other in &recs[i + 1..] {
+					assert_eq!(overlap(*rec, *other), 0, "{:?} {:?}", rec, other);
+				}
+			}
+			assert_eq!(recs.iter().copied().map(area).sum::<i64>(), area(full));
+		}
+	}
+}
@@ -0,0 +1,156 @@
+// Copyright 2023 the Strata authors
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+	cell::RefCell,
+	rc::Rc,
+};
+
+use smithay::{
+	desktop::Window,
+	utils::{
+		Logical,
+		Rectangle,
+	},
+};
+
+use super::{
+	Layout,
+	WindowList,
+};
+use crate::{
+	config::{
+		LayoutKind,
+		Master,
+		MasterSide,
+		Tiling,
+	},
+	tiling::split_rec,
+	workspaces::{
+		Direction,
+		HorizontalOrVertical,
+		StrataWindow,
+	},
+};
+
+/// Puts the first `master.count` windows side by side with the stack of the others. With
+/// only masters or only stacked windows, they share the whole area.
+pub struct MasterStack {
+	windows: WindowList,
+	master: Master,
+}
+
+impl MasterStack {
+	pub fn new(master: Master) -> Self {
+		MasterStack { windows: WindowList::new(), master }
+	}
+}
+
+impl Layout for MasterStack {
+	fn kind(&self) -> LayoutKind {
+		LayoutKind::MasterStack
+	}
+
+	fn windows(&self) -> Vec<Rc<RefCell<StrataWindow>>> {
+		self.windows.windows()
+	}
+
+	fn insert(&mut self, window: Rc<RefCell<StrataWindow>>) {
+		self.windows.push(window);
+	}
+
+	fn insert_beside(
+		&mut self,
+		window: Rc<RefCell<StrataWindow>>,
+		target: &Window,
+		direction: Direction,
+		_ratio: f32,
+	) {
+		self.windows.insert_beside(window, target, direction);
+	}
+
+	fn remove(&mut self, window: &Window) {
+		self.windows.remove(window);
+	}
+
+	fn arrange(&self, area: Rectangle<i32, Logical>, inner: i32) -> Vec<Rectangle<i32, Logical>> {
+		let count = (self.master.count as usize).min(self.windows.len());
+		let stacked = self.windows.len() - count;
+
+		let (split, stack_split) = match self.master.side {
+			MasterSide::Left | MasterSide::Right => {
+				(HorizontalOrVertical::Horizontal, HorizontalOrVertical::Vertical)
+			}
+			MasterSide::Top | MasterSide::Bottom => {
+				(HorizontalOrVertical::Vertical, HorizontalOrVertical::Horizontal)
+			}
+		};
+
+		let mut recs = Vec::with_capacity(self.windows.len());
+		if count == 0 || stacked == 0 {
+			stack(self.windows.len(), area, stack_split, inner, &mut recs);
+			return recs;
+		}
+
+		let ratio = self.master.ratio;
+		let (master_area, stack_area) = match self.master.side {
+			MasterSide::Left | MasterSide::Top => split_rec(area, split, ratio, inner),
+			MasterSide::Right | MasterSide::Bottom => {
+				let (stack_area, master_area) = split_rec(area, split, 1.0 - ratio, inner);
+				(master_area, stack_area)
+			}
+		};
+		stack(count, master_area, stack_split, inner, &mut recs);
+		stack(stacked, stack_area, stack_split, inner, &mut recs);
+		recs
+	}
+
+	fn swap(&mut self, window: &Window, other: &Window) -> bool {
+		self.windows.swap(window, other)
+	}
+
+	fn move_to(&mut self, window: &Window, target: &Window) -> bool {
+		self.windows.move_to(window, target)
+	}
+
+	/// Makes `window` the first master. The first master trades places with the next window
+	/// instead.
+	fn promote(&mut self, window: &Window) -> bool {
+		if self.windows.position(window) != Some(0) {
+			return self.windows.promote(window);
+		}
+		if let Some(next) = self.windows.get(1) {
+			self.windows.swap(window, &next);
+		}
+		true
+	}
+
+	fn configure(&mut self, tiling: &Tiling) {
+		self.master = tiling.master;
+	}
+
+	fn master(&mut self) -> Option<&mut Master> {
+		Some(&mut self.master)
+	}
+}
+
+/// Gives `count` windows an equal part of `area`, one after another along `split`.
+pub(super) fn stack(
+	count: usize,
+	area: Rectangle<i32, Logical>,
+	split: HorizontalOrVertical,
+	inner: i32,
+	recs: &mut Vec<Rectangle<i32, Logical>>,
+) {
+	let mut rest = area;
+	for i in 0..count {
+		let remaining = count - i;
+		if remaining == 1 {
+			recs.push(rest);
+		} else {
+			let (first, second) = split_rec(rest, split, 1.0 / remaining as f32, inner);
+			recs.push(first);
+			rest = second;
+		}
+	}
+}
@@ -1,4 +1,278 @@
 // Copyright 2023 the Strata authors
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use std::{
+	cell::RefCell,
+	rc::Rc,
+};
+
+use smithay::{
+	desktop::Window,
+	utils::{
+		Logical,
+		Rectangle,
+	},
+};
+
+use crate::{
+	config::{
+		LayoutKind,
+		Master,
+		Tiling,
+	},
+	workspaces::{
+		Direction,
+		Dwindle,
+		HorizontalOrVertical,
+		StrataWindow,
+	},
+};
+
+pub mod custom;
 pub mod dwindle;
+pub mod grid;
+pub mod master_stack;
+pub mod monocle;
+pub mod scrolling;
+pub mod spiral;
+#[cfg(test)]
+mod testing;
+
+use self::{
+	custom::CustomLayout,
+	grid::Grid,
+	master_stack::MasterStack,
+	monocle::Monocle,
+	scrolling::Scrolling,
+	spiral::Spiral,
+};
+
+/// Arranges the tiled windows of a workspace. Floating windows are never part of a layout.
+///
+/// Only inserting, removing, arranging and swapping are required, the other operations do
+/// nothing unless the layout has a use for them.
+pub trait Layout {
+	fn kind(&self) -> LayoutKind;
+
+	/// The windows of the layout, in the order `arrange` places them.
+	fn windows(&self) -> Vec<Rc<RefCell<StrataWindow>>>;
+
+	fn contains(&self, window: &Window) -> bool {
+		self.windows().iter().any(|w| w.borrow().smithay_window == *window)
+	}
+
+	fn insert(&mut self, window: Rc<RefCell<StrataWindow>>);
+
+	/// Inserts `window` on the `direction` side of `target`, giving it `ratio` of the area of
+	/// `target` if the layout can.
+	fn insert_beside(
+		&mut self,
+		window: Rc<RefCell<StrataWindow>>,
+		_target: &Window,
+		_direction: Direction,
+		_ratio: f32,
+	) {
+		self.insert(window);
+	}
+
+	fn remove(&mut self, window: &Window);
+
+	/// Gives each window its part of `area`, in the order of `windows`, leaving `inner` pixels
+	/// between neighbours.
+	fn arrange(&self, area: Rectangle<i32, Logical>, inner: i32) -> Vec<Rectangle<i32, Logical>>;
+
+	/// The window focus goes to from `window` in `direction`, by default the closest one on
+	/// that side as of the last arrangement.
+	fn neighbour(&self, window: &Window, direction: Direction) -> Option<Window> {
+		let windows = self.windows();
+		let from = windows.iter().find(|w| w.borrow().smithay_window == *window)?.borrow().rec;
+		windows
+			.iter()
+			.map(|w| w.borrow())
+			.filter(|w| w.smithay_window != *window)
+			.filter_map(|w| {
+				directional_distance(from, w.rec, direction).map(|d| (d, w.smithay_window.clone()))
+			})
+			.min_by_key(|(distance, _)| *distance)
+			.map(|(_, window)| window)
+	}
+
+	fn swap(&mut self, window: &Window, other: &Window) -> bool;
+
+	/// Tells the layout that `window` got keyboard focus.
+	fn focus(&mut self, _window: &Window) {}
+
+	/// Whether `window` is shown. Hidden windows keep their place in the layout but are
+	/// neither drawn nor found under the pointer.
+	fn is_visible(&self, _window: &Window) -> bool {
+		true
+	}
+
+	/// Where the tab bar listing the windows goes, for layouts that have one.
+	fn tab_bar(&self) -> Option<Rectangle<i32, Logical>> {
+		None
+	}
+
+	/// Moves `window` to the place of `target`. Layouts that can't shift the windows in
+	/// between swap both instead.
+	fn move_to(&mut self, window: &Window, target: &Window) -> bool {
+		self.swap(window, target)
+	}
+
+	/// Moves `window` to the master position, wherever the layout has one.
+	fn promote(&mut self, _window: &Window) -> bool {
+		false
+	}
+
+	/// Moves the split along `orientation` between `window` and its neighbour by `delta`
+	/// pixels. With `window_is_first`, the window is on the first (left or top) side of the
+	/// split, so its right or bottom edge moves; otherwise its left or top edge does. Returns
+	/// `false` if the window has no such split.
+	fn resize(
+		&mut self,
+		_window: &Window,
+		_orientation: HorizontalOrVertical,
+		_window_is_first: bool,
+		_delta: f64,
+	) -> bool {
+		false
+	}
+
+	fn toggle_split(&mut self, _window: &Window) -> bool {
+		false
+	}
+
+	fn reset_ratios(&mut self) {}
+
+	/// Gives the column of `window` the next of the preset widths, for layouts with columns.
+	fn cycle_width(&mut self, _window: &Window) -> bool {
+		false
+	}
+
+	/// Takes the settings of the layout from the config again.
+	fn configure(&mut self, _tiling: &Tiling) {}
+
+	/// The master settings, for layouts that have a master area.
+	fn master(&mut self) -> Option<&mut Master> {
+		None
+	}
+
+	fn custom(&mut self) -> Option<&mut CustomLayout> {
+		None
+	}
+}
+
+/// The windows of a layout that keeps them in a list and lays them out in its order.
+#[derive(Default)]
+pub struct WindowList(Vec<Rc<RefCell<StrataWindow>>>);
+
+impl WindowList {
+	pub fn new() -> Self {
+		WindowList::default()
+	}
+
+	pub fn windows(&self) -> Vec<Rc<RefCell<StrataWindow>>> {
+		self.0.clone()
+	}
+
+	pub fn len(&self) -> usize {
+		self.0.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+
+	pub fn get(&self, index: usize) -> Option<Window> {
+		self.0.get(index).map(|w| w.borrow().smithay_window.clone())
+	}
+
+	pub fn position(&self, window: &Window) -> Option<usize> {
+		self.0.iter().position(|w| w.borrow().smithay_window == *window)
+	}
+
+	pub fn push(&mut self, window: Rc<RefCell<StrataWindow>>) {
+		self.0.push(window);
+	}
+
+	/// Puts `window` before `target` for the left and up directions, after it otherwise.
+	pub fn insert_beside(
+		&mut self,
+		window: Rc<RefCell<StrataWindow>>,
+		target: &Window,
+		direction: Direction,
+	) {
+		match self.position(target) {
+			Some(index) if direction.is_first() => self.0.insert(index, window),
+			Some(index) => self.0.insert(index + 1, window),
+			None => self.0.push(window),
+		}
+	}
+
+	pub fn remove(&mut self, window: &Window) {
+		self.0.retain(|w| w.borrow().smithay_window != *window);
+	}
+
+	pub fn swap(&mut self, window: &Window, other: &Window) -> bool {
+		let (Some(a), Some(b)) = (self.position(window), self.position(other)) else {
+			return false;
+		};
+		self.0.swap(a, b);
+		true
+	}
+
+	/// Moves `window` to the place of `target`, shifting the windows in between by one.
+	pub fn move_to(&mut self, window: &Window, target: &Window) -> bool {
+		let (Some(index), Some(target)) = (self.position(window), self.position(target)) else {
+			return false;
+		};
+		let window = self.0.remove(index);
+		self.0.insert(target, window);
+		true
+	}
+
+	/// Moves `window` to the front of the list.
+	pub fn promote(&mut self, window: &Window) -> bool {
+		let Some(index) = self.position(window) else {
+			return false;
+		};
+		let window = self.0.remove(index);
+		self.0.insert(0, window);
+		true
+	}
+}
+
+/// An empty layout of the given kind, set up as in `tiling`.
+pub fn new_layout(kind: LayoutKind, tiling: &Tiling) -> Box<dyn Layout> {
+	match kind {
+		LayoutKind::Dwindle => Box::new(Dwindle::new()),
+		LayoutKind::MasterStack => Box::new(MasterStack::new(tiling.master)),
+		LayoutKind::Monocle => Box::new(Monocle::new(tiling.monocle)),
+		LayoutKind::Scrolling => Box::new(Scrolling::new(tiling.scrolling.clone())),
+		LayoutKind::Grid => Box::new(Grid::new()),
+		LayoutKind::Spiral => Box::new(Spiral::new()),
+		LayoutKind::Custom(name) => Box::new(CustomLayout::new(name)),
+	}
+}
+
+/// Ranks `to` as seen from `from` in `direction`: first whether it lies beside `from`, then
+/// how far away it is, then how far their centers are apart across the direction. `None` if
+/// `to` isn't in that direction at all.
+pub fn directional_distance(
+	from: Rectangle<i32, Logical>,
+	to: Rectangle<i32, Logical>,
+	direction: Direction,
+) -> Option<(bool, i32, i32)> {
+	let overlaps_x = to.loc.x < from.loc.x + from.size.w && from.loc.x < to.loc.x + to.size.w;
+	let overlaps_y = to.loc.y < from.loc.y + from.size.h && from.loc.y < to.loc.y + to.size.h;
+	let offset_x = ((to.loc.x * 2 + to.size.w) - (from.loc.x * 2 + from.size.w)).abs() / 2;
+	let offset_y = ((to.loc.y * 2 + to.size.h) - (from.loc.y * 2 + from.size.h)).abs() / 2;
+
+	let (distance, overlaps, offset) = match direction {
+		Direction::Left => (from.loc.x - (to.loc.x + to.size.w), overlaps_y, offset_y),
+		Direction::Right => (to.loc.x - (from.loc.x + from.size.w), overlaps_y, offset_y),
+		Direction::Up => (from.loc.y - (to.loc.y + to.size.h), overlaps_x, offset_x),
+		Direction::Down => (to.loc.y - (from.loc.y + from.size.h), overlaps_x, offset_x),
+	};
+	(distance >= 0).then_some((!overlaps, distance, offset))
+}
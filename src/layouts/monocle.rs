@@ -0,0 +1,141 @@
+// Copyright 2023 the Strata authors
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+	cell::{
+		Cell,
+		RefCell,
+	},
+	rc::Rc,
+};
+
+use smithay::{
+	desktop::Window,
+	utils::{
+		Logical,
+		Rectangle,
+	},
+};
+
+use super::{
+	Layout,
+	WindowList,
+};
+use crate::{
+	config::{
+		self,
+		LayoutKind,
+		Tiling,
+	},
+	workspaces::{
+		Direction,
+		StrataWindow,
+	},
+};
+
+/// Gives every window the whole area and only shows the current one, the window that last
+/// had focus. The tab bar, if enabled, takes the top of the area.
+pub struct Monocle {
+	windows: WindowList,
+	current: Option<Window>,
+	settings: config::Monocle,
+	/// The area of the tab bar as of the last arrangement.
+	tab_bar: Cell<Option<Rectangle<i32, Logical>>>,
+}
+
+impl Monocle {
+	pub fn new(settings: config::Monocle) -> Self {
+		Monocle { windows: WindowList::new(), current: None, settings, tab_bar: Cell::new(None) }
+	}
+
+	/// The index of the shown window, the first one if none had focus yet.
+	fn current_index(&self) -> Option<usize> {
+		let current = self.current.as_ref().and_then(|current| self.windows.position(current));
+		current.or((!self.windows.is_empty()).then_some(0))
+	}
+}
+
+impl Layout for Monocle {
+	fn kind(&self) -> LayoutKind {
+		LayoutKind::Monocle
+	}
+
+	fn windows(&self) -> Vec<Rc<RefCell<StrataWindow>>> {
+		self.windows.windows()
+	}
+
+	fn insert(&mut self, window: Rc<RefCell<StrataWindow>>) {
+		self.windows.push(window);
+	}
+
+	fn insert_beside(
+		&mut self,
+		window: Rc<RefCell<StrataWindow>>,
+		target: &Window,
+		direction: Direction,
+		_ratio: f32,
+	) {
+		self.windows.insert_beside(window, target, direction);
+	}
+
+	fn remove(&mut self, window: &Window) {
+		self.windows.remove(window);
+		if self.current.as_ref() == Some(window) {
+			self.current = None;
+		}
+	}
+
+	fn arrange(&self, area: Rectangle<i32, Logical>, inner: i32) -> Vec<Rectangle<i32, Logical>> {
+		let mut area = area;
+		let tab_height = self.settings.tab_height;
+		if self.settings.tab_bar && !self.windows.is_empty() && area.size.h > tab_height + inner {
+			self.tab_bar
+				.set(Some(Rectangle::from_loc_and_size(area.loc, (area.size.w, tab_height))));
+			area.loc.y += tab_height + inner;
+			area.size.h -= tab_height + inner;
+		} else {
+			self.tab_bar.set(None);
+		}
+		vec![area; self.windows.len()]
+	}
+
+	/// Left and up go to the previous window, right and down to the next one.
+	fn neighbour(&self, window: &Window, direction: Direction) -> Option<Window> {
+		let index = self.windows.position(window)?;
+		let index = if direction.is_first() { index.checked_sub(1)? } else { index + 1 };
+		self.windows.get(index)
+	}
+
+	fn swap(&mut self, window: &Window, other: &Window) -> bool {
+		self.windows.swap(window, other)
+	}
+
+	fn move_to(&mut self, window: &Window, target: &Window) -> bool {
+		self.windows.move_to(window, target)
+	}
+
+	/// Makes `window` the first tab.
+	fn promote(&mut self, window: &Window) -> bool {
+		self.windows.promote(window)
+	}
+
+	fn focus(&mut self, window: &Window) {
+		if self.windows.position(window).is_some() {
+			self.current = Some(window.clone());
+		}
+	}
+
+	fn is_visible(&self, window: &Window) -> bool {
+		self.current_index()
+			.and_then(|index| self.windows.get(index))
+			.is_some_and(|shown| shown == *window)
+	}
+
+	fn tab_bar(&self) -> Option<Rectangle<i32, Logical>> {
+		self.tab_bar.get()
+	}
+
+	fn configure(&mut self, tiling: &Tiling) {
+		self.settings = tiling.monocle;
+	}
+}
@@ -0,0 +1,226 @@
+// Copyright 2023 the Strata authors
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+	cell::RefCell,
+	rc::Rc,
+};
+
+use smithay::{
+	desktop::Window,
+	utils::{
+		Logical,
+		Rectangle,
+	},
+};
+
+use super::{
+	master_stack::stack,
+	Layout,
+};
+use crate::{
+	config::{
+		self,
+		LayoutKind,
+		Tiling,
+	},
+	workspaces::{
+		Direction,
+		HorizontalOrVertical,
+		StrataWindow,
+	},
+};
+
+/// Puts windows in columns side by side, from the left of the area onwards, however far that
+/// goes. The workspace scrolls the columns that don't fit into view as they get focus.
+pub struct Scrolling {
+	columns: Vec<Column>,
+	/// The window that last had focus, new columns open to its right.
+	current: Option<Window>,
+	settings: config::Scrolling,
+}
+
+/// Windows stacked on top of each other, sharing the height of the area.
+struct Column {
+	windows: Vec<Rc<RefCell<StrataWindow>>>,
+	/// Which of the preset widths the column has.
+	width: usize,
+}
+
+impl Column {
+	fn new(window: Rc<RefCell<StrataWindow>>) -> Self {
+		Column { windows: vec![window], width: 0 }
+	}
+
+	fn position(&self, window: &Window) -> Option<usize> {
+		self.windows.iter().position(|w| w.borrow().smithay_window == *window)
+	}
+}
+
+impl Scrolling {
+	pub fn new(settings: config::Scrolling) -> Self {
+		Scrolling { columns: Vec::new(), current: None, settings }
+	}
+
+	/// The column and the place within it of `window`.
+	fn position(&self, window: &Window) -> Option<(usize, usize)> {
+		self.columns
+			.iter()
+			.enumerate()
+			.find_map(|(i, column)| column.position(window).map(|row| (i, row)))
+	}
+
+	/// The width of `column` in an area `width` pixels wide. Columns of the same width as the
+	/// area fill it exactly, as do two columns of half its width and the gap between them.
+	fn column_width(&self, column: &Column, width: i32, inner: i32) -> i32 {
+		let ratio = self
+			.settings
+			.widths
+			.get(column.width)
+			.or(self.settings.widths.first())
+			.copied()
+			.unwrap_or(1.0)
+			.clamp(0.1, 1.0);
+		(((width + inner) as f32 * ratio) as i32 - inner).max(1)
+	}
+
+	/// Takes `window` out of its column, dropping the column if it was the last window in it.
+	fn take(&mut self, window: &Window) -> Option<Rc<RefCell<StrataWindow>>> {
+		let (column, row) = self.position(window)?;
+		let window = self.columns[column].windows.remove(row);
+		if self.columns[column].windows.is_empty() {
+			self.columns.remove(column);
+		}
+		Some(window)
+	}
+}
+
+impl Layout for Scrolling {
+	fn kind(&self) -> LayoutKind {
+		LayoutKind::Scrolling
+	}
+
+	fn windows(&self) -> Vec<Rc<RefCell<StrataWindow>>> {
+		self.columns.iter().flat_map(|column| column.windows.iter().cloned()).collect()
+	}
+
+	/// Opens a column for `window` to the right of the focused one.
+	fn insert(&mut self, window: Rc<RefCell<StrataWindow>>) {
+		let index = match self.current.as_ref().and_then(|current| self.position(current)) {
+			Some((column, _)) => column + 1,
+			None => self.columns.len(),
+		};
+		self.columns.insert(index, Column::new(window));
+	}
+
+	/// Opens a column for `window` on the left or right of the column of `target`, or puts it
+	/// above or below `target` in the same column.
+	fn insert_beside(
+		&mut self,
+		window: Rc<RefCell<StrataWindow>>,
+		target: &Window,
+		direction: Direction,
+		_ratio: f32,
+	) {
+		let Some((column, row)) = self.position(target) else {
+			self.insert(window);
+			return;
+		};
+		match direction {
+			Direction::Left => self.columns.insert(column, Column::new(window)),
+			Direction::Right => self.columns.insert(column + 1, Column::new(window)),
+			Direction::Up => self.columns[column].windows.insert(row, window),
+			Direction::Down => self.columns[column].windows.insert(row + 1, window),
+		}
+	}
+
+	fn remove(&mut self, window: &Window) {
+		self.take(window);
+		if self.current.as_ref() == Some(window) {
+			self.current = None;
+		}
+	}
+
+	fn arrange(&self, area: Rectangle<i32, Logical>, inner: i32) -> Vec<Rectangle<i32, Logical>> {
+		let mut recs = Vec::new();
+		let mut x = area.loc.x;
+		for column in self.columns.iter() {
+			let width = self.column_width(column, area.size.w, inner);
+			let column_area = Rectangle::from_loc_and_size((x, area.loc.y), (width, area.size.h));
+			stack(
+				column.windows.len(),
+				column_area,
+				HorizontalOrVertical::Vertical,
+				inner,
+				&mut recs,
+			);
+			x += width + inner;
+		}
+		recs
+	}
+
+	/// Left and right go to the next column, to the window at the same height in it if there
+	/// is one. Up and down stay within the column.
+	fn neighbour(&self, window: &Window, direction: Direction) -> Option<Window> {
+		let (column, row) = self.position(window)?;
+		let (column, row) = match direction {
+			Direction::Left => (column.checked_sub(1)?, row),
+			Direction::Right => (column + 1, row),
+			Direction::Up => (column, row.checked_sub(1)?),
+			Direction::Down => (column, row + 1),
+		};
+		let windows = &self.columns.get(column)?.windows;
+		let window = match direction {
+			Direction::Left | Direction::Right => windows.get(row).or(windows.last()),
+			Direction::Up | Direction::Down => windows.get(row),
+		};
+		window.map(|w| w.borrow().smithay_window.clone())
+	}
+
+	fn swap(&mut self, window: &Window, other: &Window) -> bool {
+		let (Some((a_column, a_row)), Some((b_column, b_row))) =
+			(self.position(window), self.position(other))
+		else {
+			return false;
+		};
+		let a = self.columns[a_column].windows[a_row].clone();
+		let b = self.columns[b_column].windows[b_row].clone();
+		self.columns[a_column].windows[a_row] = b;
+		self.columns[b_column].windows[b_row] = a;
+		true
+	}
+
+	/// Moves `window` into the column of `target`, at its place. This is how windows end up
+	/// stacked in a column; a column is gone once its last window left it.
+	fn move_to(&mut self, window: &Window, target: &Window) -> bool {
+		if window == target || self.position(target).is_none() {
+			return false;
+		}
+		let Some(moved) = self.take(window) else {
+			return false;
+		};
+		let (column, row) = self.position(target).unwrap();
+		self.columns[column].windows.insert(row, moved);
+		true
+	}
+
+	fn focus(&mut self, window: &Window) {
+		if self.position(window).is_some() {
+			self.current = Some(window.clone());
+		}
+	}
+
+	fn cycle_width(&mut self, window: &Window) -> bool {
+		let Some((column, _)) = self.position(window) else {
+			return false;
+		};
+		let count = self.settings.widths.len().max(1);
+		let column = &mut self.columns[column];
+		column.width = (column.width + 1) % count;
+		true
+	}
+
+	fn configure(&mut self, tiling: &Tiling) {
+		self.settings = tiling.scrolling.clone();
+	}
+}
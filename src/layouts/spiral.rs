@@ -0,0 +1,182 @@
+// Copyright 2023 the Strata authors
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::{
+	cell::RefCell,
+	rc::Rc,
+};
+
+use smithay::{
+	desktop::Window,
+	utils::{
+		Logical,
+		Rectangle,
+	},
+};
+
+use super::{
+	Layout,
+	WindowList,
+};
+use crate::{
+	config::LayoutKind,
+	tiling::split_rec,
+	workspaces::{
+		Direction,
+		HorizontalOrVertical,
+		StrataWindow,
+	},
+};
+
+/// Each window takes half of what the windows before it left over, on the left, top, right
+/// and bottom in turn, so the windows spiral inwards clockwise. The last window gets all
+/// that is left.
+#[derive(Default)]
+pub struct Spiral {
+	windows: WindowList,
+}
+
+impl Spiral {
+	pub fn new() -> Self {
+		Spiral::default()
+	}
+}
+
+/// The rectangles of `count` windows spiralling inwards in `area`.
+fn spiral(count: usize, area: Rectangle<i32, Logical>, inner: i32) -> Vec<Rectangle<i32, Logical>> {
+	let mut recs = Vec::with_capacity(count);
+	let mut rest = area;
+	for i in 0..count {
+		if i + 1 == count {
+			recs.push(rest);
+			break;
+		}
+		let split = match i % 2 {
+			0 => HorizontalOrVertical::Horizontal,
+			_ => HorizontalOrVertical::Vertical,
+		};
+		let (first, second) = split_rec(rest, split, 0.5, inner);
+		// left and top, then right and bottom
+		if i % 4 < 2 {
+			recs.push(first);
+			rest = second;
+		} else {
+			recs.push(second);
+			rest = first;
+		}
+	}
+	recs
+}
+
+impl Layout for Spiral {
+	fn kind(&self) -> LayoutKind {
+		LayoutKind::Spiral
+	}
+
+	fn windows(&self) -> Vec<Rc<RefCell<StrataWindow>>> {
+		self.windows.windows()
+	}
+
+	fn insert(&mut self, window: Rc<RefCell<StrataWindow>>) {
+		self.windows.push(window);
+	}
+
+	fn insert_beside(
+		&mut self,
+		window: Rc<RefCell<StrataWindow>>,
+		target: &Window,
+		direction: Direction,
+		_ratio: f32,
+	) {
+		self.windows.insert_beside(window, target, direction);
+	}
+
+	fn remove(&mut self, window: &Window) {
+		self.windows.remove(window);
+	}
+
+	fn arrange(&self, area: Rectangle<i32, Logical>, inner: i32) -> Vec<Rectangle<i32, Logical>> {
+		spiral(self.windows.len(), area, inner)
+	}
+
+	fn swap(&mut self, window: &Window, other: &Window) -> bool {
+		self.windows.swap(window, other)
+	}
+
+	fn move_to(&mut self, window: &Window, target: &Window) -> bool {
+		self.windows.move_to(window, target)
+	}
+
+	/// Makes `window` the first one, which takes the biggest part of the area.
+	fn promote(&mut self, window: &Window) -> bool {
+		self.windows.promote(window)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use proptest::prelude::*;
+	use smithay::utils::Rectangle;
+
+	use super::spiral;
+	use crate::layouts::testing::{
+		area,
+		inside,
+		overlap,
+	};
+
+	#[test]
+	fn turns_clockwise() {
+		let full = Rectangle::from_loc_and_size((0, 0), (800, 400));
+		assert_eq!(
+			spiral(4, full, 0),
+			vec![
+				Rectangle::from_loc_and_size((0, 0), (400, 400)),
+				Rectangle::from_loc_and_size((400, 0), (400, 200)),
+				Rectangle::from_loc_and_size((600, 200), (200, 200)),
+				Rectangle::from_loc_and_size((400, 200), (200, 200)),
+			]
+		);
+	}
+
+	proptest! {
+		#[test]
+		fn covers_the_area(
+			count in 0usize..32,
+			x in -2000..2000,
+			y in -2000..2000,
+			w in 1..4000,
+			h in 1..4000,
+		) {
+			let full = Rectangle::from_loc_and_size((x, y), (w, h));
+			let recs = spiral(count, full, 0);
+
+			prop_assert_eq!(recs.len(), count);
+			for (i, rec) in recs.iter().enumerate() {
+				prop_assert!(rec.size.w >= 0 && rec.size.h >= 0, "{:?}", rec);
+				prop_assert!(inside(*rec, full), "{:?}", rec);
+				for other in &recs[i + 1..] {
+					prop_assert_eq!(overlap(*rec, *other), 0, "{:?} {:?}", rec, other);
+				}
+			}
+			if count > 0 {
+				prop_assert_eq!(recs.iter().copied().map(area).sum::<i64>(), area(full));
+			}
+		}
+
+		#[test]
+		fn leaves_gaps(count in 0usize..32, inner in 0..50, w in 1..4000, h in 1..4000) {
+			let full = Rectangle::from_loc_and_size((0, 0), (w, h));
+			let recs = spiral(count, full, inner);
+
+			prop_assert_eq!(recs.len(), count);
+			for (i, rec) in recs.iter().enumerate() {
+				prop_assert!(rec.size.w >= 0 && rec.size.h >= 0, "{:?}", rec);
+				prop_assert!(inside(*rec, full), "{:?}", rec);
+				for other in &recs[i + 1..] {
+					prop_assert_eq!(overlap(*rec, *other), 0, "{:?} {:?}", rec, other);
+				}
+			}
+		}
+	}
+}
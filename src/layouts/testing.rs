@@ -0,0 +1,26 @@
+// Copyright 2023 the Strata authors
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Checks on the rectangles layouts produce, shared by their tests.
+
+use smithay::utils::{
+	Logical,
+	Rectangle,
+};
+
+pub fn inside(rec: Rectangle<i32, Logical>, area: Rectangle<i32, Logical>) -> bool {
+	rec.loc.x >= area.loc.x
+		&& rec.loc.y >= area.loc.y
+		&& rec.loc.x + rec.size.w <= area.loc.x + area.size.w
+		&& rec.loc.y + rec.size.h <= area.loc.y + area.size.h
+}
+
+pub fn area(rec: Rectangle<i32, Logical>) -> i64 {
+	rec.size.w as i64 * rec.size.h as i64
+}
+
+pub fn overlap(a: Rectangle<i32, Logical>, b: Rectangle<i32, Logical>) -> i64 {
+	let w = (a.loc.x + a.size.w).min(b.loc.x + b.size.w) - a.loc.x.max(b.loc.x);
+	let h = (a.loc.y + a.size.h).min(b.loc.y + b.size.h) - a.loc.y.max(b.loc.y);
+	w.max(0) as i64 * h.max(0) as i64
+}
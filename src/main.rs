@@ -4,6 +4,7 @@
 use std::{
 	error::Error,
 	io::stdout,
+	path::PathBuf,
 };
 
 use chrono::Local;
@@ -18,6 +19,7 @@ pub mod bindings;
 pub mod config;
 pub mod decorations;
 pub mod handlers;
+pub mod ipc;
 pub mod layouts;
 pub mod state;
 pub mod tiling;
@@ -29,20 +31,23 @@ pub mod workspaces;
 pub struct Args {
 	#[arg(short, long)]
 	pub backend: String,
+	/// Config file to load instead of `$XDG_CONFIG_HOME/strata/config.lua`
+	#[arg(short, long)]
+	pub config: Option<PathBuf>,
+	/// Size of each virtual output of the headless backend, as `WIDTHxHEIGHT`
+	#[arg(long, default_value = "1920x1080")]
+	pub output_size: String,
+	/// Number of virtual outputs of the headless backend
+	#[arg(long, default_value_t = 1)]
+	pub outputs: u8,
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
 	let args = Args::parse();
 	let xdg = xdg::BaseDirectories::with_prefix("strata")?;
-	// let config_dir = xdg.find_config_file("");
-	// let lib_dir = xdg.find_data_file("lua");
 	let log_dir = xdg.get_state_home();
 
-	// if let (Some(config_path), Some(data_path)) = (config_dir, lib_dir) {
-	// 	tokio::spawn(async { parse_config(config_path, data_path) }).await??;
-	// }
-
 	let file_appender = tracing_appender::rolling::never(
 		&log_dir,
 		format!("strata_{}.log", Local::now().format("%Y-%m-%d_%H:%M:%S")),
@@ -61,7 +66,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
 	info!("Parsing config...");
 	info!("Initializing socket interface...");
 
-	init_with_backend(&args.backend);
+	init_with_backend(&args)?;
 
 	info!("Quitting Strata WM");
 
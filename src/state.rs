@@ -15,21 +15,20 @@ use std::{
 	},
 };
 
+use log::{
+	debug,
+	error,
+	info,
+};
 use piccolo as lua;
 use smithay::{
-	backend::{
-		input::{
-			Event,
-			InputBackend,
-			InputEvent,
-			KeyState,
-			KeyboardKeyEvent,
-		},
-		renderer::{
-			damage::OutputDamageTracker,
-			glow::GlowRenderer,
-		},
-		winit::WinitGraphicsBackend,
+	backend::input::{
+		Device,
+		Event,
+		InputBackend,
+		InputEvent,
+		KeyState,
+		KeyboardKeyEvent,
 	},
 	desktop::{
 		layer_map_for_output,
@@ -46,6 +45,7 @@ use smithay::{
 		Seat,
 		SeatState,
 	},
+	output::Output,
 	reexports::{
 		calloop::{
 			generic::{
@@ -54,6 +54,7 @@ use smithay::{
 			},
 			EventLoop,
 			Interest,
+			LoopHandle,
 			LoopSignal,
 			Mode,
 			PostAction,
@@ -71,7 +72,6 @@ use smithay::{
 	utils::{
 		Logical,
 		Point,
-		Rectangle,
 		SERIAL_COUNTER,
 	},
 	wayland::{
@@ -100,13 +100,21 @@ use smithay::{
 };
 
 use crate::{
-	decorations::BorderShader,
+	backends::Backend,
+	bindings,
+	config::{
+		Config,
+		ConfigPaths,
+	},
 	handlers::input::{
 		KeyPattern,
 		ModFlags,
 		Mods,
 	},
+	ipc,
 	workspaces::{
+		output_geometry,
+		Direction,
 		FocusTarget,
 		Workspaces,
 	},
@@ -116,9 +124,31 @@ pub struct StrataState {
 	pub lua: lua::Lua,
 	pub comp: Rc<RefCell<StrataComp>>,
 	pub display: Display<StrataComp>,
+	pub config_paths: ConfigPaths,
 }
 
 impl StrataState {
+	pub fn new(comp: StrataComp, display: Display<StrataComp>, config_paths: ConfigPaths) -> Self {
+		let mut lua_vm = lua::Lua::full();
+		let comp = Rc::new(RefCell::new(comp));
+
+		lua_vm.try_enter(|ctx| Ok(bindings::register(ctx, Rc::clone(&comp))?)).unwrap();
+
+		let mut state = StrataState { lua: lua_vm, comp, display, config_paths };
+		state.load_config();
+		state.init_config_watcher();
+
+		let (loop_handle, socket_name) = {
+			let comp = state.comp.borrow();
+			(comp.loop_handle.clone(), comp.socket_name.clone())
+		};
+		if let Err(err) = ipc::init_ipc_listener(&loop_handle, &socket_name) {
+			error!("Failed to initialize the IPC socket: {:#}", err);
+		}
+
+		state
+	}
+
 	pub fn process_input_event<I: InputBackend>(
 		&mut self,
 		event: InputEvent<I>,
@@ -131,31 +161,15 @@ impl StrataState {
 			}
 			InputEvent::PointerButton { event, .. } => self.pointer_button::<I>(event)?,
 			InputEvent::PointerAxis { event, .. } => self.pointer_axis::<I>(event)?,
-			InputEvent::DeviceAdded { device: _ } => {
-				// todo
-				println!("device added");
+			InputEvent::DeviceAdded { device } => info!("Input device added: {}", device.name()),
+			InputEvent::DeviceRemoved { device } => {
+				info!("Input device removed: {}", device.name())
 			}
-			InputEvent::DeviceRemoved { device: _ } => todo!(),
-			InputEvent::GestureSwipeBegin { event: _ } => todo!(),
-			InputEvent::GestureSwipeUpdate { event: _ } => todo!(),
-			InputEvent::GestureSwipeEnd { event: _ } => todo!(),
-			InputEvent::GesturePinchBegin { event: _ } => todo!(),
-			InputEvent::GesturePinchUpdate { event: _ } => todo!(),
-			InputEvent::GesturePinchEnd { event: _ } => todo!(),
-			InputEvent::GestureHoldBegin { event: _ } => todo!(),
-			InputEvent::GestureHoldEnd { event: _ } => todo!(),
-			InputEvent::TouchDown { event: _ } => todo!(),
-			InputEvent::TouchMotion { event: _ } => todo!(),
-			InputEvent::TouchUp { event: _ } => todo!(),
-			InputEvent::TouchCancel { event: _ } => todo!(),
-			InputEvent::TouchFrame { event: _ } => todo!(),
-			InputEvent::TabletToolAxis { event: _ } => todo!(),
-			InputEvent::TabletToolProximity { event: _ } => todo!(),
-			InputEvent::TabletToolTip { event: _ } => todo!(),
-			InputEvent::TabletToolButton { event: _ } => todo!(),
-			InputEvent::Special(_) => todo!(),
-			// _ => anyhow::bail!("unhandled winit event: {:#?}", &event),
+			// gestures, touch and tablets aren't supported yet
+			_ => debug!("Ignoring unsupported input event"),
 		};
+		// input can change focus, borders and layouts, all of which need a new frame
+		self.comp.borrow_mut().schedule_render();
 
 		Ok(())
 	}
@@ -180,13 +194,20 @@ impl StrataState {
 				// println!("{:#?}({:#?})", event.state(), keysym_h.modified_sym());
 				match event.state() {
 					KeyState::Pressed => {
+						let keysym = keysym_h.modified_sym().raw();
+						let vts = Keysym::XF86_Switch_VT_1.raw()..=Keysym::XF86_Switch_VT_12.raw();
+						if vts.contains(&keysym) {
+							comp.backend.change_vt((keysym - vts.start() + 1) as i32);
+							return FilterResult::Intercept(None);
+						}
+
 						let k = KeyPattern {
 							mods: comp.mods.flags,
 							key: keysym_h.modified_sym().into(),
 						};
 
 						if let Some(f) = comp.config.keybinds.get(&k) {
-							return FilterResult::Intercept(f.clone());
+							return FilterResult::Intercept(Some(f.clone()));
 						}
 
 						FilterResult::Forward
@@ -198,7 +219,7 @@ impl StrataState {
 			},
 		);
 
-		if let Some(f) = f {
+		if let Some(f) = f.flatten() {
 			let ex = self.lua.try_enter(|ctx| {
 				let f = ctx.fetch(&f);
 				Ok(ctx.stash(lua::Executor::start(ctx, f, ())))
@@ -249,9 +270,9 @@ impl StrataState {
 
 pub struct StrataComp {
 	pub dh: DisplayHandle,
-	pub backend: WinitGraphicsBackend<GlowRenderer>,
-	pub damage_tracker: OutputDamageTracker,
+	pub backend: Backend,
 	pub start_time: Instant,
+	pub loop_handle: LoopHandle<'static, StrataState>,
 	pub loop_signal: LoopSignal,
 	pub compositor_state: CompositorState,
 	pub xdg_shell_state: XdgShellState,
@@ -276,11 +297,11 @@ impl StrataComp {
 		display: &Display<StrataComp>,
 		socket_name: OsString,
 		seat_name: String,
-		backend: WinitGraphicsBackend<GlowRenderer>,
-		damage_tracker: OutputDamageTracker,
+		backend: Backend,
 	) -> Self {
 		let start_time = Instant::now();
 		let dh = display.handle();
+		let loop_handle = event_loop.handle();
 		let loop_signal = event_loop.get_signal();
 		let compositor_state = CompositorState::new::<Self>(&dh);
 		let xdg_shell_state = XdgShellState::new::<Self>(&dh);
@@ -313,12 +334,12 @@ impl StrataComp {
 		StrataComp {
 			dh,
 			backend,
-			damage_tracker,
 			start_time,
 			socket_name,
 			compositor_state,
 			xdg_shell_state,
 			xdg_decoration_state,
+			loop_handle,
 			loop_signal,
 			shm_state,
 			output_manager_state,
@@ -330,36 +351,42 @@ impl StrataComp {
 			seat,
 			workspaces,
 			mods: Mods { flags: ModFlags::empty(), state: mods_state },
-			config: StrataConfig { keybinds: HashMap::new() },
+			config: StrataConfig::default(),
 		}
 	}
 
-	fn winit_render(&mut self) {
-		let render_elements = self.workspaces.current().render_elements(self.backend.renderer());
-		self.damage_tracker
-			.render_output(self.backend.renderer(), 0, &render_elements, [0.1, 0.1, 0.1, 1.0])
-			.unwrap();
-	}
 	pub fn surface_under(&self) -> Option<(FocusTarget, Point<i32, Logical>)> {
-		let pos = self.seat.get_pointer().unwrap().current_location();
-		let output = self.workspaces.current().outputs().find(|o| {
-			let geometry = self.workspaces.current().output_geometry(o).unwrap();
-			geometry.contains(pos.to_i32_round())
-		})?;
-		let output_geo = self.workspaces.current().output_geometry(output).unwrap();
+		self.surface_at(self.seat.get_pointer().unwrap().current_location())
+	}
+
+	pub fn surface_at(
+		&self,
+		pos: Point<f64, Logical>,
+	) -> Option<(FocusTarget, Point<i32, Logical>)> {
+		let output = self.workspaces.output_at(pos)?;
+		let output_geo = output_geometry(output)?;
+		let workspace = self.workspaces.on_output(output)?;
 		let layers = layer_map_for_output(output);
+		// layer surfaces are placed relative to their output
+		let layer_pos = pos - output_geo.loc.to_f64();
+
+		// fullscreen windows are drawn between the Overlay and Top layers
+		let fullscreen = workspace.fullscreen_window_under(pos);
 
 		let mut under = None;
-		if let Some(layer) =
-			layers.layer_under(Layer::Overlay, pos).or_else(|| layers.layer_under(Layer::Top, pos))
-		{
+		if let Some(layer) = layers.layer_under(Layer::Overlay, layer_pos) {
+			let layer_loc = layers.layer_geometry(layer).unwrap().loc;
+			under = Some((layer.clone().into(), output_geo.loc + layer_loc))
+		} else if let Some((window, location)) = fullscreen {
+			under = Some((window.clone().into(), location));
+		} else if let Some(layer) = layers.layer_under(Layer::Top, layer_pos) {
 			let layer_loc = layers.layer_geometry(layer).unwrap().loc;
 			under = Some((layer.clone().into(), output_geo.loc + layer_loc))
-		} else if let Some((window, location)) = self.workspaces.current().window_under(pos) {
+		} else if let Some((window, location)) = workspace.window_under(pos) {
 			under = Some((window.clone().into(), location));
 		} else if let Some(layer) = layers
-			.layer_under(Layer::Bottom, pos)
-			.or_else(|| layers.layer_under(Layer::Background, pos))
+			.layer_under(Layer::Bottom, layer_pos)
+			.or_else(|| layers.layer_under(Layer::Background, layer_pos))
 		{
 			let layer_loc = layers.layer_geometry(layer).unwrap().loc;
 			under = Some((layer.clone().into(), output_geo.loc + layer_loc));
@@ -367,49 +394,137 @@ impl StrataComp {
 		under
 	}
 
-	pub fn winit_update(&mut self) {
-		self.winit_render();
-
-		self.set_input_focus_auto();
-
-		// damage tracking
-		let size = self.backend.window_size();
-		let damage = Rectangle::from_loc_and_size((0, 0), size);
-		self.backend.bind().unwrap();
-		self.backend.submit(Some(&[damage])).unwrap();
-
-		// sync and cleanups
-		let output = self.workspaces.current().outputs().next().unwrap();
-		self.workspaces.current().windows().for_each(|window| {
-			window.send_frame(output, self.start_time.elapsed(), Some(Duration::ZERO), |_, _| {
-				Some(output.clone())
+	pub fn post_repaint(&mut self, output: &Output) {
+		if let Some(workspace) = self.workspaces.on_output(output) {
+			workspace.windows().for_each(|window| {
+				window.send_frame(
+					output,
+					self.start_time.elapsed(),
+					Some(Duration::ZERO),
+					|_, _| Some(output.clone()),
+				);
+
+				window.refresh();
 			});
-
-			window.refresh();
-		});
+		}
 		self.dh.flush_clients().unwrap();
 		self.popup_manager.cleanup();
-		BorderShader::cleanup(self.backend.renderer());
 	}
 
 	pub fn close_window(&mut self) {
-		let ptr = self.seat.get_pointer().unwrap();
-		if let Some((window, _)) = self.workspaces.current().window_under(ptr.current_location()) {
+		if let Some(window) = self.focused_window() {
 			window.toplevel().send_close()
 		}
 	}
 
+	pub fn set_floating(&mut self, floating: bool) {
+		let Some(window) = self.focused_window() else {
+			return;
+		};
+		if let Some(workspace) = self.workspaces.workspace_from_window(&window) {
+			workspace.set_floating(&window, floating);
+		}
+	}
+
+	pub fn toggle_floating(&mut self) {
+		let Some(window) = self.focused_window() else {
+			return;
+		};
+		if let Some(workspace) = self.workspaces.workspace_from_window(&window) {
+			workspace.toggle_floating(&window);
+		}
+	}
+
+	pub fn center_window(&mut self) {
+		let Some(window) = self.focused_window() else {
+			return;
+		};
+		if let Some(workspace) = self.workspaces.workspace_from_window(&window) {
+			workspace.center_window(&window);
+		}
+	}
+
+	pub fn set_fullscreen(&mut self, fullscreen: bool) {
+		let Some(window) = self.focused_window() else {
+			return;
+		};
+		if let Some(workspace) = self.workspaces.workspace_from_window(&window) {
+			workspace.set_fullscreen(&window, fullscreen);
+		}
+	}
+
+	pub fn toggle_fullscreen(&mut self) {
+		let Some(window) = self.focused_window() else {
+			return;
+		};
+		if let Some(workspace) = self.workspaces.workspace_from_window(&window) {
+			workspace.toggle_fullscreen(&window);
+		}
+	}
+
+	pub fn set_maximized(&mut self, maximized: bool) {
+		let Some(window) = self.focused_window() else {
+			return;
+		};
+		if let Some(workspace) = self.workspaces.workspace_from_window(&window) {
+			workspace.set_maximized(&window, maximized);
+		}
+	}
+
+	pub fn toggle_maximized(&mut self) {
+		let Some(window) = self.focused_window() else {
+			return;
+		};
+		if let Some(workspace) = self.workspaces.workspace_from_window(&window) {
+			workspace.toggle_maximized(&window);
+		}
+	}
+
+	pub fn grow_window(&mut self, direction: Direction, amount: i32) {
+		let Some(window) = self.focused_window() else {
+			return;
+		};
+		if let Some(workspace) = self.workspaces.workspace_from_window(&window) {
+			workspace.grow_window(&window, direction, amount);
+		}
+	}
+
+	pub fn toggle_split(&mut self) {
+		let Some(window) = self.focused_window() else {
+			return;
+		};
+		if let Some(workspace) = self.workspaces.workspace_from_window(&window) {
+			workspace.toggle_split(&window);
+		}
+	}
+
+	pub fn cycle_width(&mut self) {
+		let Some(window) = self.focused_window() else {
+			return;
+		};
+		if let Some(workspace) = self.workspaces.workspace_from_window(&window) {
+			workspace.cycle_width(&window);
+		}
+	}
+
+	pub fn preselect(&mut self, direction: Direction, ratio: f32) {
+		let Some(window) = self.focused_window() else {
+			return;
+		};
+		if let Some(workspace) = self.workspaces.workspace_from_window(&window) {
+			workspace.preselect(&window, direction, ratio);
+		}
+	}
+
 	pub fn switch_to_workspace(&mut self, id: u8) {
 		self.workspaces.activate(id);
-		self.set_input_focus_auto();
+		self.restore_focus();
 	}
 
 	pub fn move_window_to_workspace(&mut self, id: u8) {
-		let pos = self.seat.get_pointer().unwrap().current_location();
-		let window = self.workspaces.current().window_under(pos).map(|d| d.0.clone());
-
-		if let Some(window) = window {
+		if let Some(window) = self.focused_window() {
 			self.workspaces.move_window_to_workspace(&window, id);
+			self.restore_focus();
 		}
 	}
 
@@ -418,6 +533,65 @@ impl StrataComp {
 		self.switch_to_workspace(id);
 	}
 
+	/// Makes the workspace on `output` the current one, moving the pointer there if
+	/// `focus.warp_pointer` is set.
+	pub fn focus_output(&mut self, output: &Output) {
+		self.workspaces.focus_output(output);
+		if let Some(geometry) =
+			output_geometry(output).filter(|_| self.config.options.focus.warp_pointer)
+		{
+			let center =
+				(geometry.loc.x + geometry.size.w / 2, geometry.loc.y + geometry.size.h / 2);
+			self.warp_pointer(Point::from(center).to_f64());
+		}
+		self.restore_focus();
+	}
+
+	/// Shows workspaces on a newly connected `output`, bringing back the ones it had before it
+	/// was disconnected.
+	pub fn add_output(&mut self, output: &Output) {
+		self.workspaces.add_output(output.clone());
+		self.restore_focus();
+		self.schedule_render();
+	}
+
+	/// Closes the layer surfaces of a disconnected `output` and moves its workspaces to the
+	/// remaining outputs. The pointer goes to the current output if it was left off them.
+	pub fn remove_output(&mut self, output: &Output) {
+		let mut map = layer_map_for_output(output);
+		let layers: Vec<_> = map.layers().cloned().collect();
+		for layer in layers {
+			map.unmap_layer(&layer);
+			layer.layer_surface().send_close();
+		}
+		drop(map);
+
+		self.workspaces.remove_output(output);
+		let pointer = self.seat.get_pointer().map(|ptr| ptr.current_location());
+		let geometry = self.workspaces.current_output().and_then(output_geometry);
+		if let (Some(pointer), Some(geometry)) = (pointer, geometry) {
+			if self.workspaces.output_at(pointer).is_none() {
+				let center =
+					(geometry.loc.x + geometry.size.w / 2, geometry.loc.y + geometry.size.h / 2);
+				self.warp_pointer(Point::from(center).to_f64());
+			}
+		}
+		self.restore_focus();
+		self.schedule_render();
+	}
+
+	pub fn move_workspace_to_output(&mut self, id: u8, output: &Output) {
+		self.workspaces.move_workspace_to_output(id, output);
+		self.restore_focus();
+	}
+
+	pub fn move_window_to_output(&mut self, output: &Output) {
+		if let Some(window) = self.focused_window() {
+			self.workspaces.move_window_to_output(&window, output);
+			self.restore_focus();
+		}
+	}
+
 	pub fn quit(&mut self) {
 		self.loop_signal.stop();
 	}
@@ -486,8 +660,30 @@ impl StrataComp {
 	}
 }
 
+#[derive(Clone, Default)]
 pub struct StrataConfig {
 	pub keybinds: HashMap<KeyPattern, lua::StashedFunction>,
+	/// Layouts registered with `strata.register_layout`, by name.
+	pub layouts: HashMap<String, lua::StashedFunction>,
+	pub options: Config,
+}
+
+impl StrataConfig {
+	/// Replaces the options and adds their bindings to the keybinds registered through
+	/// `strata.input.Key`. Nothing changes if one of the bindings is invalid.
+	pub fn set_options(&mut self, options: Config) -> anyhow::Result<()> {
+		let bindings = options
+			.bindings
+			.iter()
+			.map(|binding| -> anyhow::Result<_> {
+				Ok((KeyPattern::from_names(&binding.keys)?, binding.action.key.clone()))
+			})
+			.collect::<anyhow::Result<Vec<_>>>()?;
+		self.keybinds.extend(bindings);
+		self.options = options;
+
+		Ok(())
+	}
 }
 
 pub fn init_wayland_listener(
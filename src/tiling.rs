@@ -6,61 +6,56 @@ use std::{
 	rc::Rc,
 };
 
-use smithay::{
-	desktop::layer_map_for_output,
-	utils::{
-		Logical,
-		Physical,
-		Point,
-		Rectangle,
-		Size,
-	},
+use smithay::utils::{
+	Logical,
+	Point,
+	Rectangle,
+	Size,
 };
 
 use crate::workspaces::{
-	Dwindle,
+	Gaps,
 	HorizontalOrVertical,
 	StrataWindow,
 	Workspace,
 };
 
 pub fn refresh_geometry(workspace: &mut Workspace) {
-	let gaps = { (3, 3) };
-	let output = layer_map_for_output(workspace.outputs().next().unwrap()).non_exclusive_zone();
-	let output_full = workspace.outputs().next().unwrap().current_mode().unwrap().size;
+	let (Some(fullscreen), Some(output)) = (workspace.output_geometry(), workspace.zone()) else {
+		return;
+	};
 
-	match &mut workspace.layout_tree {
-		Dwindle::Empty => {}
-		Dwindle::Window(w) => {
-			w.borrow_mut().rec = Rectangle {
-				loc: Point::from((gaps.0 + gaps.1 + output.loc.x, gaps.0 + gaps.1 + output.loc.y)),
-				size: Size::from((
-					output.size.w - ((gaps.0 + gaps.1) * 2),
-					output.size.h - ((gaps.0 + gaps.1) * 2),
-				)),
-			};
-		}
-		Dwindle::Split { left, right, split, ratio } => {
-			if let Dwindle::Window(w) = left.as_mut() {
-				generate_layout(
-					right.as_mut(),
-					w,
-					Rectangle {
-						loc: Point::from((gaps.0 + output.loc.x, gaps.0 + output.loc.y)),
-						size: Size::from((
-							output.size.w - (gaps.0 * 2),
-							output.size.h - (gaps.0 * 2),
-						)),
-					},
-					*split,
-					*ratio,
-					Size::from((output_full.w - gaps.0, output_full.h - gaps.0)),
-					gaps,
-				)
-			}
+	// smart gaps: a lone window takes the whole output
+	let windows = workspace.layout.windows();
+	let gaps = match windows.len() {
+		1 if workspace.gaps.smart => Gaps { inner: 0, outer: 0, smart: true },
+		_ => workspace.gaps,
+	};
+	// leave at least a pixel to the windows when the outer gaps don't fit the output
+	let outer = gaps.outer.clamp(0, (output.size.w.min(output.size.h) - 1).max(0) / 2);
+	let area = Rectangle {
+		loc: Point::from((output.loc.x + outer, output.loc.y + outer)),
+		size: Size::from((output.size.w - outer * 2, output.size.h - outer * 2)),
+	};
+
+	let recs = workspace.layout.arrange(area, gaps.inner);
+	workspace.viewport = viewport(workspace, &windows, &recs, area);
+	for (window, mut rec) in windows.iter().zip(recs) {
+		rec.loc.x -= workspace.viewport;
+		window.borrow_mut().rec = rec;
+	}
+	for strata_window in workspace.windows.iter() {
+		let mut strata_window = strata_window.borrow_mut();
+		if strata_window.fullscreen {
+			strata_window.rec = fullscreen;
+		} else if strata_window.maximized {
+			strata_window.rec = output;
 		}
 	}
-	for strata_window in workspace.strata_windows() {
+
+	for strata_window in
+		workspace.strata_windows().filter(|w| !w.floating || w.fullscreen || w.maximized)
+	{
 		let xdg_toplevel = strata_window.smithay_window.toplevel();
 		xdg_toplevel.with_pending_state(|state| {
 			state.size = Some(strata_window.rec.size);
@@ -69,54 +64,61 @@ pub fn refresh_geometry(workspace: &mut Workspace) {
 	}
 }
 
-pub fn generate_layout(
-	tree: &mut Dwindle,
-	lastwin: &Rc<RefCell<StrataWindow>>,
-	lastgeo: Rectangle<i32, Logical>,
+/// How far to scroll a layout wider than `area` to have the focused window in view, scrolling
+/// as little as possible from where it was. Layouts that fit aren't scrolled.
+fn viewport(
+	workspace: &Workspace,
+	windows: &[Rc<RefCell<StrataWindow>>],
+	recs: &[Rectangle<i32, Logical>],
+	area: Rectangle<i32, Logical>,
+) -> i32 {
+	let end = recs.iter().map(|rec| rec.loc.x + rec.size.w).max().unwrap_or(area.loc.x);
+	let max = (end - area.loc.x - area.size.w).max(0);
+
+	let mut viewport = workspace.viewport;
+	let focused = workspace
+		.focused_window()
+		.and_then(|focused| windows.iter().position(|w| w.borrow().smithay_window == focused));
+	if let Some(rec) = focused.and_then(|i| recs.get(i)) {
+		let left = rec.loc.x - area.loc.x;
+		let right = left + rec.size.w;
+		if left < viewport || rec.size.w > area.size.w {
+			viewport = left;
+		} else if right > viewport + area.size.w {
+			viewport = right - area.size.w;
+		}
+	}
+	viewport.clamp(0, max)
+}
+
+/// Splits `rec` in two along `split`, leaving `inner` pixels between both halves. Rectangles
+/// too small for the gap are split without it, so both halves stay within `rec`.
+pub fn split_rec(
+	rec: Rectangle<i32, Logical>,
 	split: HorizontalOrVertical,
 	ratio: f32,
-	output: Size<i32, Physical>,
-	gaps: (i32, i32),
-) {
-	let size = match split {
+	inner: i32,
+) -> (Rectangle<i32, Logical>, Rectangle<i32, Logical>) {
+	match split {
 		HorizontalOrVertical::Horizontal => {
-			Size::from(((lastgeo.size.w as f32 * ratio) as i32, lastgeo.size.h))
+			let inner = if inner > rec.size.w { 0 } else { inner };
+			let w = ((rec.size.w - inner) as f32 * ratio) as i32;
+			let first = Rectangle::from_loc_and_size(rec.loc, (w, rec.size.h));
+			let second = Rectangle::from_loc_and_size(
+				(rec.loc.x + w + inner, rec.loc.y),
+				(rec.size.w - w - inner, rec.size.h),
+			);
+			(first, second)
 		}
 		HorizontalOrVertical::Vertical => {
-			Size::from((lastgeo.size.w, (lastgeo.size.h as f32 * ratio) as i32))
-		}
-	};
-
-	let loc: Point<i32, Logical> = match split {
-		HorizontalOrVertical::Horizontal => Point::from((lastgeo.loc.x, output.h - size.h)),
-		HorizontalOrVertical::Vertical => Point::from((output.w - size.w, lastgeo.loc.y)),
-	};
-
-	let rec_with_gaps = Rectangle {
-		size: Size::from((size.w - (gaps.1 * 2), (size.h - (gaps.1 * 2)))),
-		loc: Point::from((loc.x + gaps.1, loc.y + gaps.1)),
-	};
-
-	lastwin.borrow_mut().rec = rec_with_gaps;
-
-	let loc = match split {
-		HorizontalOrVertical::Horizontal => Point::from((output.w - size.w, lastgeo.loc.y)),
-		HorizontalOrVertical::Vertical => Point::from((lastgeo.loc.x, output.h - size.h)),
-	};
-
-	let rec = Rectangle { size, loc };
-	let rec_with_gaps = Rectangle {
-		size: Size::from((size.w - (gaps.1 * 2), (size.h - (gaps.1 * 2)))),
-		loc: Point::from((loc.x + gaps.1, loc.y + gaps.1)),
-	};
-	match tree {
-		Dwindle::Empty => {}
-		Dwindle::Window(w) => w.borrow_mut().rec = rec_with_gaps,
-		Dwindle::Split { split, ratio, left, right } => {
-			if let Dwindle::Window(w) = left.as_mut() {
-				w.borrow_mut().rec = rec;
-				generate_layout(right.as_mut(), w, rec, *split, *ratio, output, gaps)
-			}
+			let inner = if inner > rec.size.h { 0 } else { inner };
+			let h = ((rec.size.h - inner) as f32 * ratio) as i32;
+			let first = Rectangle::from_loc_and_size(rec.loc, (rec.size.w, h));
+			let second = Rectangle::from_loc_and_size(
+				(rec.loc.x, rec.loc.y + h + inner),
+				(rec.size.w, rec.size.h - h - inner),
+			);
+			(first, second)
 		}
 	}
 }
@@ -11,8 +11,18 @@ use std::{
 
 use smithay::{
 	backend::renderer::{
-		element::AsRenderElements,
+		element::{
+			solid::{
+				SolidColorBuffer,
+				SolidColorRenderElement,
+			},
+			surface::WaylandSurfaceRenderElement,
+			utils::CropRenderElement,
+			AsRenderElements,
+			Kind,
+		},
 		ImportAll,
+		ImportMem,
 		Renderer,
 		Texture,
 	},
@@ -24,54 +34,173 @@ use smithay::{
 		Window,
 	},
 	output::Output,
+	reexports::wayland_protocols::xdg::shell::server::xdg_toplevel::State as ToplevelState,
 	utils::{
 		Logical,
 		Point,
 		Rectangle,
 		Scale,
+		Size,
 		Transform,
 	},
-	wayland::shell::wlr_layer::Layer,
+	wayland::shell::{
+		wlr_layer::Layer,
+		xdg::ToplevelSurface,
+	},
+};
+
+use strata_derive::Config;
+use strum::{
+	AsRefStr,
+	EnumString,
 };
 
 use crate::{
+	config::{
+		Border,
+		General,
+		LayoutKind,
+		Master,
+		Tiling,
+	},
 	decorations::{
 		AsGlowRenderer,
 		BorderShader,
 		CustomRenderElements,
+		TabBar,
+	},
+	handlers::{
+		grabs::ResizeEdge,
+		xdg_shell::{
+			initial_configure_sent,
+			set_tiled_states,
+			title_and_app_id,
+		},
+	},
+	layouts::{
+		directional_distance,
+		dwindle::{
+			MAX_RATIO,
+			MIN_RATIO,
+		},
+		new_layout,
+		Layout,
+	},
+	tiling::{
+		refresh_geometry,
+		split_rec,
 	},
-	tiling::refresh_geometry,
 };
 
 pub struct StrataWindow {
 	pub smithay_window: Window,
 	pub rec: Rectangle<i32, Logical>,
+	/// Floating windows are left out of the layout tree, their `rec` is only changed on request.
+	pub floating: bool,
+	/// Fullscreen windows cover their whole output, maximized ones the area not taken by layer
+	/// surfaces. Both keep their place in the layout to go back to.
+	pub fullscreen: bool,
+	pub maximized: bool,
+	/// Where a floating window was before it was maximized or made fullscreen.
+	restore_rec: Option<Rectangle<i32, Logical>>,
 }
 
 pub struct Workspace {
 	pub windows: Vec<Rc<RefCell<StrataWindow>>>,
-	pub outputs: Vec<Output>,
-	pub layout_tree: Dwindle,
+	/// The output the workspace is shown on whenever it's active there.
+	pub output: Option<Output>,
+	/// The name of the output the workspace was on before that output was disconnected. It
+	/// goes back there once the output is connected again.
+	home: Option<String>,
+	/// Arranges the windows that don't float.
+	pub layout: Box<dyn Layout>,
+	pub gaps: Gaps,
+	/// How far the layout is scrolled to the left, for layouts wider than the output.
+	pub viewport: i32,
+	/// Windows in the order they were focused, the most recent last.
+	focus_history: Vec<Window>,
+	preselection: Option<Preselection>,
+	tab_bar: RefCell<TabBar>,
+}
+
+/// The settings of a workspace that a config can change, to go back to when a new config
+/// fails to load.
+pub struct WorkspaceSnapshot {
+	gaps: Gaps,
+	layout: LayoutKind,
+	master: Option<Master>,
+}
+
+/// The preview of a preselection is drawn in the active border color at this opacity.
+const PRESELECTION_ALPHA: f32 = 0.3;
+
+/// Where the next tiled window opens: next to `window`, on its `direction` side.
+pub struct Preselection {
+	pub window: Window,
+	pub direction: Direction,
+	/// The share of the area of `window` that goes to the new window.
+	pub ratio: f32,
+	/// Keeps the preview's id stable across frames, for damage tracking.
+	buffer: RefCell<SolidColorBuffer>,
+}
+
+/// `inner` is the space between two windows, `outer` the space between windows and the
+/// output edges. With `smart` set, a workspace holding a single window has no gaps.
+#[derive(Debug, Clone, Copy, PartialEq, Config)]
+pub struct Gaps {
+	pub inner: i32,
+	pub outer: i32,
+	pub smart: bool,
 }
 
 pub struct Workspaces {
 	pub workspaces: Vec<Workspace>,
+	/// The workspace with keyboard focus, on the focused output.
 	pub current: u8,
+	/// The connected outputs from left to right, each with the workspace it shows. Outputs
+	/// only show nothing when there are more outputs than workspaces.
+	pub outputs: Vec<(Output, Option<u8>)>,
+	/// The names of the disconnected outputs, each with the workspace it showed.
+	disconnected: Vec<(String, Option<u8>)>,
 }
 
-#[derive(Clone)]
-pub enum Dwindle {
+/// A binary tree of splits with a window in each leaf. Either side of a split can be another
+/// split. Only a tree without windows is `Empty`.
+pub enum Dwindle<W = Rc<RefCell<StrataWindow>>> {
 	Empty,
-	Window(Rc<RefCell<StrataWindow>>),
-	Split { split: HorizontalOrVertical, ratio: f32, left: Box<Dwindle>, right: Box<Dwindle> },
+	Window(W),
+	Split { split: HorizontalOrVertical, ratio: f32, left: Box<Dwindle<W>>, right: Box<Dwindle<W>> },
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum HorizontalOrVertical {
 	Horizontal,
 	Vertical,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, EnumString, AsRefStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum Direction {
+	Left,
+	Right,
+	Up,
+	Down,
+}
+
+impl Direction {
+	pub fn orientation(self) -> HorizontalOrVertical {
+		match self {
+			Direction::Left | Direction::Right => HorizontalOrVertical::Horizontal,
+			Direction::Up | Direction::Down => HorizontalOrVertical::Vertical,
+		}
+	}
+
+	/// Whether the direction points at the first side of a split, its left or top.
+	pub fn is_first(self) -> bool {
+		matches!(self, Direction::Left | Direction::Up)
+	}
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum FocusTarget {
 	Window(Window),
@@ -80,6 +209,36 @@ pub enum FocusTarget {
 }
 
 impl StrataWindow {
+	pub fn new(window: Window) -> Self {
+		StrataWindow {
+			rec: window.geometry(),
+			smithay_window: window,
+			floating: false,
+			fullscreen: false,
+			maximized: false,
+			restore_rec: None,
+		}
+	}
+
+	/// Whether the window floats and takes the geometry it's given by the user.
+	fn floats_freely(&self) -> bool {
+		self.floating && !self.fullscreen && !self.maximized
+	}
+
+	/// Moves a floating window by `offset`, including where it goes back to from fullscreen
+	/// or maximized. Tiled windows are placed by their layout instead.
+	fn shift(&mut self, offset: Point<i32, Logical>) {
+		if !self.floating {
+			return;
+		}
+		if let Some(restore_rec) = self.restore_rec.as_mut() {
+			restore_rec.loc += offset;
+		}
+		if self.floats_freely() {
+			self.rec.loc += offset;
+		}
+	}
+
 	fn bbox(&self) -> Rectangle<i32, Logical> {
 		let mut bbox = self.smithay_window.bbox();
 		bbox.loc += self.rec.loc - self.smithay_window.geometry().loc;
@@ -90,9 +249,45 @@ impl StrataWindow {
 		self.rec.loc - self.smithay_window.geometry().loc
 	}
 }
+impl From<&General> for Gaps {
+	fn from(general: &General) -> Self {
+		Gaps { inner: general.gaps_in, outer: general.gaps_out, smart: general.smart_gaps }
+	}
+}
+
+impl Gaps {
+	/// Rejects negative gaps. Outer gaps too large for an output are clamped when laying out.
+	pub fn validate(self) -> anyhow::Result<Self> {
+		if self.inner < 0 || self.outer < 0 {
+			anyhow::bail!(
+				"gaps can't be negative, got inner {} and outer {}",
+				self.inner,
+				self.outer
+			);
+		}
+		Ok(self)
+	}
+}
+
+impl Default for Gaps {
+	fn default() -> Self {
+		Gaps::from(&General::default())
+	}
+}
+
 impl Workspace {
 	pub fn new() -> Self {
-		Workspace { windows: Vec::new(), outputs: Vec::new(), layout_tree: Dwindle::new() }
+		Workspace {
+			windows: Vec::new(),
+			output: None,
+			home: None,
+			layout: Box::new(Dwindle::new()),
+			gaps: Gaps::default(),
+			viewport: 0,
+			focus_history: Vec::new(),
+			preselection: None,
+			tab_bar: RefCell::new(TabBar::default()),
+		}
 	}
 
 	pub fn windows(&self) -> impl Iterator<Item = Ref<'_, Window>> {
@@ -103,11 +298,480 @@ impl Workspace {
 		self.windows.iter().map(|w| Ref::map(w.borrow(), |hw| hw))
 	}
 
+	/// Windows from top to bottom: fullscreen ones, floating ones, most recently raised first,
+	/// maximized ones and then the other tiled ones. Tiled windows the layout hides are left
+	/// out.
+	fn stacked_windows(&self) -> impl Iterator<Item = &Rc<RefCell<StrataWindow>>> {
+		let fullscreen = self.windows.iter().rev().filter(|w| w.borrow().fullscreen);
+		let floating = self.windows.iter().rev().filter(|w| {
+			let w = w.borrow();
+			w.floating && !w.fullscreen
+		});
+		let maximized = self.windows.iter().rev().filter(|w| {
+			let w = w.borrow();
+			!w.floating && !w.fullscreen && w.maximized && self.layout.is_visible(&w.smithay_window)
+		});
+		let tiled = self.windows.iter().filter(|w| {
+			let w = w.borrow();
+			!w.floating
+				&& !w.fullscreen
+				&& !w.maximized
+				&& self.layout.is_visible(&w.smithay_window)
+		});
+		fullscreen.chain(floating).chain(maximized).chain(tiled)
+	}
+
+	fn strata_window(&self, window: &Window) -> Option<Rc<RefCell<StrataWindow>>> {
+		self.windows.iter().find(|w| &w.borrow().smithay_window == window).cloned()
+	}
+
+	/// The window that last had keyboard focus on this workspace.
+	pub fn focused_window(&self) -> Option<Window> {
+		self.focus_history.last().cloned()
+	}
+
+	pub fn record_focus(&mut self, window: &Window) {
+		self.focus_history.retain(|w| w != window);
+		self.focus_history.push(window.clone());
+		self.layout.focus(window);
+
+		// layouts wider than the output scroll the window into view
+		let Some(zone) = self.zone() else {
+			return;
+		};
+		let out_of_view = self.strata_window(window).is_some_and(|w| {
+			let w = w.borrow();
+			!w.floating && !w.fullscreen && !w.maximized && !zone.contains_rect(w.rec)
+		});
+		if out_of_view {
+			refresh_geometry(self);
+		}
+	}
+
 	pub fn add_window(&mut self, window: Rc<RefCell<StrataWindow>>) {
 		self.windows.retain(|w| w.borrow().smithay_window != window.borrow().smithay_window);
 		self.windows.push(window.clone());
-		self.layout_tree.insert(window, self.layout_tree.next_split(), 0.5);
+		if !window.borrow().floating {
+			self.insert_tiled(window);
+		}
+		refresh_geometry(self);
+	}
+
+	/// Adds a window to the layout, next to the preselected window if there is one.
+	fn insert_tiled(&mut self, window: Rc<RefCell<StrataWindow>>) {
+		match self.preselection.take() {
+			Some(presel) => {
+				self.layout.insert_beside(window, &presel.window, presel.direction, presel.ratio)
+			}
+			None => self.layout.insert(window),
+		}
+	}
+
+	/// Switches to a layout of another kind, which gets the windows of the current one in
+	/// the same order.
+	pub fn set_layout(&mut self, kind: LayoutKind, tiling: &Tiling) {
+		if self.layout.kind() == kind {
+			return;
+		}
+		let mut layout = new_layout(kind, tiling);
+		for window in self.layout.windows() {
+			layout.insert(window);
+		}
+		self.layout = layout;
+		refresh_geometry(self);
+	}
+
+	pub fn snapshot(&mut self) -> WorkspaceSnapshot {
+		WorkspaceSnapshot {
+			gaps: self.gaps,
+			layout: self.layout.kind(),
+			master: self.layout.master().copied(),
+		}
+	}
+
+	/// Goes back to the settings in `snapshot`, taking those it doesn't hold from `tiling`.
+	pub fn restore(&mut self, snapshot: WorkspaceSnapshot, tiling: &Tiling) {
+		self.gaps = snapshot.gaps;
+		self.set_layout(snapshot.layout, tiling);
+		self.layout.configure(tiling);
+		if let (Some(master), Some(saved)) = (self.layout.master(), snapshot.master) {
+			*master = saved;
+		}
+		// the layout function may have been registered again by the failed config
+		if let Some(custom) = self.layout.custom() {
+			custom.invalidate();
+		}
+		refresh_geometry(self);
+	}
+
+	pub fn is_floating(&self, window: &Window) -> bool {
+		self.strata_window(window).is_some_and(|w| w.borrow().floating)
+	}
+
+	pub fn set_floating(&mut self, window: &Window, floating: bool) {
+		let Some(strata_window) = self.strata_window(window) else {
+			return;
+		};
+		if strata_window.borrow().floating == floating {
+			return;
+		}
+		strata_window.borrow_mut().floating = floating;
+
+		let toplevel = window.toplevel();
+		if floating {
+			self.layout.remove(window);
+			// an empty size lets the client pick its preferred one, which `floating_commit`
+			// picks up and centers
+			strata_window.borrow_mut().rec.size = Size::default();
+			let bounds = self.zone().map(|zone| zone.size);
+			toplevel.with_pending_state(|state| {
+				state.size = None;
+				state.bounds = bounds;
+			});
+			self.raise_window(window);
+		} else {
+			self.insert_tiled(strata_window);
+		}
+		set_tiled_states(toplevel, !floating);
+		if initial_configure_sent(toplevel) {
+			toplevel.send_configure();
+		}
+
+		refresh_geometry(self);
+	}
+
+	pub fn toggle_floating(&mut self, window: &Window) {
+		self.set_floating(window, !self.is_floating(window));
+	}
+
+	pub fn is_fullscreen(&self, window: &Window) -> bool {
+		self.strata_window(window).is_some_and(|w| w.borrow().fullscreen)
+	}
+
+	pub fn set_fullscreen(&mut self, window: &Window, fullscreen: bool) {
+		self.change_mode(window, |w| w.fullscreen = fullscreen);
+	}
+
+	pub fn toggle_fullscreen(&mut self, window: &Window) {
+		self.set_fullscreen(window, !self.is_fullscreen(window));
+	}
+
+	pub fn is_maximized(&self, window: &Window) -> bool {
+		self.strata_window(window).is_some_and(|w| w.borrow().maximized)
+	}
+
+	pub fn set_maximized(&mut self, window: &Window, maximized: bool) {
+		self.change_mode(window, |w| w.maximized = maximized);
+	}
+
+	pub fn toggle_maximized(&mut self, window: &Window) {
+		self.set_maximized(window, !self.is_maximized(window));
+	}
+
+	/// Applies `change` to the fullscreen and maximized flags of a window. Floating windows
+	/// get their geometry back once they are neither, tiled ones their place in the layout.
+	/// Clients expect a configure in response to their requests, so one is always sent.
+	fn change_mode(&mut self, window: &Window, change: impl FnOnce(&mut StrataWindow)) {
+		let Some(strata_window) = self.strata_window(window) else {
+			return;
+		};
+
+		let (fullscreen, maximized, restored) = {
+			let mut strata_window = strata_window.borrow_mut();
+			let was_free = strata_window.floats_freely();
+			change(&mut strata_window);
+			let restored = if !strata_window.floating {
+				None
+			} else if was_free && !strata_window.floats_freely() {
+				strata_window.restore_rec = Some(strata_window.rec);
+				None
+			} else if !was_free && strata_window.floats_freely() {
+				strata_window.restore_rec.take()
+			} else {
+				None
+			};
+			if let Some(rec) = restored {
+				strata_window.rec = rec;
+			}
+			(strata_window.fullscreen, strata_window.maximized, restored)
+		};
+
+		let toplevel = window.toplevel();
+		toplevel.with_pending_state(|state| {
+			for (mode, enabled) in
+				[(ToplevelState::Fullscreen, fullscreen), (ToplevelState::Maximized, maximized)]
+			{
+				if enabled {
+					state.states.set(mode);
+				} else {
+					state.states.unset(mode);
+				}
+			}
+			if let Some(rec) = restored {
+				state.size = Some(rec.size);
+			}
+		});
+		if fullscreen || maximized {
+			self.raise_window(window);
+		}
+		refresh_geometry(self);
+		if initial_configure_sent(toplevel) {
+			toplevel.send_configure();
+		}
+	}
+
+	/// Moves a floating window to the center of the area not taken by layer surfaces.
+	pub fn center_window(&mut self, window: &Window) {
+		let Some(strata_window) = self.strata_window(window) else {
+			return;
+		};
+		let Some(zone) = self.zone() else {
+			return;
+		};
+
+		let mut strata_window = strata_window.borrow_mut();
+		if !strata_window.floats_freely() {
+			return;
+		}
+		let size = strata_window.rec.size;
+		strata_window.rec.loc =
+			zone.loc + Point::from(((zone.size.w - size.w) / 2, (zone.size.h - size.h) / 2));
+	}
+
+	pub fn window_geometry(&self, window: &Window) -> Option<Rectangle<i32, Logical>> {
+		self.strata_window(window).map(|w| w.borrow().rec)
+	}
+
+	pub fn move_floating(&mut self, window: &Window, loc: Point<i32, Logical>) {
+		if let Some(strata_window) = self.strata_window(window) {
+			let mut strata_window = strata_window.borrow_mut();
+			if strata_window.floats_freely() {
+				strata_window.rec.loc = loc;
+			}
+		}
+	}
+
+	/// Asks a floating window for the size of `rec`. It is moved to `rec.loc` right away, its
+	/// size follows on its next commit.
+	pub fn resize_floating(&mut self, window: &Window, rec: Rectangle<i32, Logical>) {
+		let Some(strata_window) = self.strata_window(window) else {
+			return;
+		};
+		if !strata_window.borrow().floats_freely() {
+			return;
+		}
+		strata_window.borrow_mut().rec.loc = rec.loc;
+
+		let toplevel = window.toplevel();
+		toplevel.with_pending_state(|state| {
+			state.size = Some(rec.size);
+			state.states.set(ToplevelState::Resizing);
+		});
+		toplevel.send_configure();
+	}
+
+	/// Moves the given edges of a tiled window by `delta`, resizing its neighbours too.
+	pub fn resize_tiled(&mut self, window: &Window, edges: ResizeEdge, delta: Point<f64, Logical>) {
+		if edges.intersects(ResizeEdge::LEFT | ResizeEdge::RIGHT) {
+			self.layout.resize(
+				window,
+				HorizontalOrVertical::Horizontal,
+				edges.contains(ResizeEdge::RIGHT),
+				delta.x,
+			);
+		}
+		if edges.intersects(ResizeEdge::TOP | ResizeEdge::BOTTOM) {
+			self.layout.resize(
+				window,
+				HorizontalOrVertical::Vertical,
+				edges.contains(ResizeEdge::BOTTOM),
+				delta.y,
+			);
+		}
+		refresh_geometry(self);
+	}
+
+	/// Grows a tiled window by `amount` pixels on its `direction` side, or shrinks it for a
+	/// negative `amount`. Windows at the edge of the layout change on the other side instead.
+	pub fn grow_window(&mut self, window: &Window, direction: Direction, amount: i32) {
+		let (orientation, window_is_first, delta) = match direction {
+			Direction::Left => (HorizontalOrVertical::Horizontal, false, -amount),
+			Direction::Right => (HorizontalOrVertical::Horizontal, true, amount),
+			Direction::Up => (HorizontalOrVertical::Vertical, false, -amount),
+			Direction::Down => (HorizontalOrVertical::Vertical, true, amount),
+		};
+		let delta = delta as f64;
+		if !self.layout.resize(window, orientation, window_is_first, delta) {
+			self.layout.resize(window, orientation, !window_is_first, -delta);
+		}
+		refresh_geometry(self);
+	}
+
+	pub fn toggle_split(&mut self, window: &Window) {
+		if self.layout.toggle_split(window) {
+			refresh_geometry(self);
+		}
+	}
+
+	pub fn cycle_width(&mut self, window: &Window) {
+		if self.layout.cycle_width(window) {
+			refresh_geometry(self);
+		}
+	}
+
+	/// Opens the next tiled window on the `direction` side of `window`, taking `ratio` of its
+	/// area. Preselecting the same side again cancels it.
+	pub fn preselect(&mut self, window: &Window, direction: Direction, ratio: f32) {
+		if !self.layout.contains(window) {
+			return;
+		}
+		if self
+			.preselection
+			.as_ref()
+			.is_some_and(|p| &p.window == window && p.direction == direction)
+		{
+			self.preselection = None;
+			return;
+		}
+
+		self.preselection = Some(Preselection {
+			window: window.clone(),
+			direction,
+			ratio: ratio.clamp(MIN_RATIO, MAX_RATIO),
+			buffer: RefCell::new(SolidColorBuffer::new(Size::default(), [0.0; 4])),
+		});
+	}
+
+	pub fn cancel_preselection(&mut self) {
+		self.preselection = None;
+	}
+
+	/// The area the preselected window will take.
+	fn preselection_area(&self) -> Option<(&Preselection, Rectangle<i32, Logical>)> {
+		let presel = self.preselection.as_ref()?;
+		let rec = self.window_geometry(&presel.window)?;
+		let area = if presel.direction.is_first() {
+			split_rec(rec, presel.direction.orientation(), presel.ratio, self.gaps.inner).0
+		} else {
+			split_rec(rec, presel.direction.orientation(), 1.0 - presel.ratio, self.gaps.inner).1
+		};
+		Some((presel, area))
+	}
+
+	/// Changes the number of master windows by `delta`, if the layout has any.
+	pub fn inc_master_count(&mut self, delta: i32) {
+		if let Some(master) = self.layout.master() {
+			master.count = master.count.saturating_add_signed(delta);
+			refresh_geometry(self);
+		}
+	}
+
+	pub fn inc_master_ratio(&mut self, delta: f32) {
+		if let Some(master) = self.layout.master() {
+			master.ratio = (master.ratio + delta).clamp(MIN_RATIO, MAX_RATIO);
+			refresh_geometry(self);
+		}
+	}
+
+	pub fn reset_ratios(&mut self) {
+		self.layout.reset_ratios();
+		refresh_geometry(self);
+	}
+
+	pub fn finish_resize(&mut self, window: &Window) {
+		let toplevel = window.toplevel();
+		toplevel.with_pending_state(|state| {
+			state.states.unset(ToplevelState::Resizing);
+		});
+		toplevel.send_configure();
+	}
+
+	/// Windows in the order `focus_next` goes through them: tiled ones as they are laid out,
+	/// then floating ones.
+	pub fn focus_order(&self) -> Vec<Window> {
+		let mut windows: Vec<_> =
+			self.layout.windows().iter().map(|w| w.borrow().smithay_window.clone()).collect();
+		windows.extend(
+			self.windows
+				.iter()
+				.filter(|w| w.borrow().floating)
+				.map(|w| w.borrow().smithay_window.clone()),
+		);
+		windows
+	}
+
+	/// The window in `direction` from `window`. The layout picks it among tiled windows,
+	/// otherwise it's the closest one, preferring windows that are next to it over ones that
+	/// are only diagonally across.
+	pub fn window_in_direction(&self, window: &Window, direction: Direction) -> Option<Window> {
+		if let Some(neighbour) = self.tiled_neighbour(window, direction) {
+			return Some(neighbour);
+		}
+		let from = self.window_geometry(window)?;
+		self.windows
+			.iter()
+			.map(|w| w.borrow())
+			.filter(|w| &w.smithay_window != window)
+			.filter_map(|w| {
+				directional_distance(from, w.rec, direction).map(|d| (d, w.smithay_window.clone()))
+			})
+			.min_by_key(|(distance, _)| *distance)
+			.map(|(_, window)| window)
+	}
+
+	/// Swaps a tiled window with its neighbour in `direction`. Returns the neighbour.
+	pub fn swap_direction(&mut self, window: &Window, direction: Direction) -> Option<Window> {
+		let target = self.tiled_neighbour(window, direction)?;
+		self.layout.swap(window, &target);
+		refresh_geometry(self);
+		Some(target)
+	}
+
+	/// Moves a tiled window to the node of its neighbour in `direction`.
+	pub fn move_direction(&mut self, window: &Window, direction: Direction) -> Option<Window> {
+		let target = self.tiled_neighbour(window, direction)?;
+		self.layout.move_to(window, &target);
 		refresh_geometry(self);
+		Some(target)
+	}
+
+	pub fn promote_window(&mut self, window: &Window) {
+		if self.layout.promote(window) {
+			refresh_geometry(self);
+		}
+	}
+
+	fn tiled_neighbour(&self, window: &Window, direction: Direction) -> Option<Window> {
+		if !self.layout.contains(window) {
+			return None;
+		}
+		self.layout.neighbour(window, direction)
+	}
+
+	/// Puts a floating window on top of the other floating windows.
+	pub fn raise_window(&mut self, window: &Window) {
+		if let Some(i) = self.windows.iter().position(|w| &w.borrow().smithay_window == window) {
+			let strata_window = self.windows.remove(i);
+			self.windows.push(strata_window);
+		}
+	}
+
+	/// Floating windows follow the size their client commits. A window that just became
+	/// floating has no size yet and is centered once it gets one.
+	pub fn floating_commit(&mut self, window: &Window) {
+		let Some(strata_window) = self.strata_window(window) else {
+			return;
+		};
+		if !strata_window.borrow().floats_freely() {
+			return;
+		}
+
+		let size = window.geometry().size;
+		let unplaced = strata_window.borrow().rec.size == Size::default();
+		strata_window.borrow_mut().rec.size = size;
+		if unplaced && size != Size::default() {
+			self.center_window(window);
+		}
 	}
 
 	pub fn remove_window(&mut self, window: &Window) -> Option<Rc<RefCell<StrataWindow>>> {
@@ -120,41 +784,37 @@ impl Workspace {
 				true
 			}
 		});
-		self.layout_tree.remove(window);
+		self.layout.remove(window);
+		self.focus_history.retain(|w| w != window);
+		if self.preselection.as_ref().is_some_and(|p| &p.window == window) {
+			self.preselection = None;
+		}
 		refresh_geometry(self);
 		removed
 	}
 
-	pub fn clamp_coords(&self, pos: Point<f64, Logical>) -> Point<f64, Logical> {
-		let Some(output) = self.outputs().next() else {
-			return pos;
-		};
-
-		let (pos_x, pos_y) = pos.into();
-		let (max_x, max_y) = self.output_geometry(output).unwrap().size.into();
-		let clamped_x = pos_x.max(0.0).min(max_x as f64);
-		let clamped_y = pos_y.max(0.0).min(max_y as f64);
-		(clamped_x, clamped_y).into()
-	}
-
-	pub fn render_elements<R: Renderer + ImportAll + AsGlowRenderer>(
+	/// The elements of the workspace and the layer surfaces of its output, relative to the
+	/// output.
+	pub fn render_elements<R: Renderer + ImportAll + ImportMem + AsGlowRenderer>(
 		&self,
 		renderer: &mut R,
+		focus: Option<&Window>,
+		border: &Border,
 	) -> Vec<CustomRenderElements<R>>
 	where
 		<R as Renderer>::TextureId: Texture + 'static,
 	{
-		let output = self.outputs().next().unwrap();
+		let (Some(output), Some(geometry)) = (self.output(), self.output_geometry()) else {
+			return Vec::new();
+		};
+		// windows are placed in the global space, the elements go on the output
+		let offset = geometry.loc;
 		let layer_map = layer_map_for_output(output);
-		let (lower, upper): (Vec<&LayerSurface>, Vec<&LayerSurface>) = layer_map
-			.layers()
-			.rev()
-			.partition(|s| matches!(s.layer(), Layer::Background | Layer::Bottom));
-
-		let mut render_elements: Vec<CustomRenderElements<_>> = Vec::new();
-		render_elements.extend(
-			upper
-				.into_iter()
+		let layer_elements = |renderer: &mut R, layers: &[Layer]| {
+			layer_map
+				.layers()
+				.rev()
+				.filter(|surface| layers.contains(&surface.layer()))
 				.filter_map(|surface| {
 					layer_map.layer_geometry(surface).map(|geo| (geo.loc, surface))
 				})
@@ -166,82 +826,177 @@ impl Workspace {
 						Scale::from(1.0),
 						1.0,
 					)
-				}),
-		);
+				})
+				.collect::<Vec<_>>()
+		};
+
+		// fullscreen windows go above Top layer surfaces (e.g. panels), but not above Overlay ones
+		let (fullscreen, windows): (Vec<_>, Vec<_>) =
+			self.stacked_windows().partition(|w| w.borrow().fullscreen);
+
+		let mut render_elements: Vec<CustomRenderElements<_>> = Vec::new();
+		render_elements.extend(layer_elements(renderer, &[Layer::Overlay]));
+		for element in fullscreen {
+			let element = element.borrow();
+			render_elements.append(&mut element.smithay_window.render_elements(
+				renderer,
+				(element.render_location() - offset).to_physical(1),
+				Scale::from(1.0),
+				1.0,
+			));
+		}
+		render_elements.extend(layer_elements(renderer, &[Layer::Top]));
+
+		if let Some((presel, area)) = self.preselection_area() {
+			let [r, g, b] = border.active.0;
+			let mut buffer = presel.buffer.borrow_mut();
+			buffer.update(
+				area.size,
+				[
+					r * PRESELECTION_ALPHA,
+					g * PRESELECTION_ALPHA,
+					b * PRESELECTION_ALPHA,
+					PRESELECTION_ALPHA,
+				],
+			);
+			render_elements.push(CustomRenderElements::Solid(
+				SolidColorRenderElement::from_buffer(
+					&buffer,
+					(area.loc - offset).to_physical(1),
+					Scale::from(1.0),
+					1.0,
+					Kind::Unspecified,
+				),
+			));
+		}
+
+		if let Some(area) = self.layout.tab_bar() {
+			let windows = self.layout.windows();
+			let titles = windows
+				.iter()
+				.map(|w| {
+					title_and_app_id(w.borrow().smithay_window.toplevel()).0.unwrap_or_default()
+				})
+				.collect();
+			let active = windows
+				.iter()
+				.position(|w| self.layout.is_visible(&w.borrow().smithay_window))
+				.unwrap_or_default();
+			let area = Rectangle::from_loc_and_size(area.loc - offset, area.size);
+			let element = self.tab_bar.borrow_mut().element(renderer, area, titles, active, border);
+			render_elements.extend(element.map(CustomRenderElements::TabBar));
+		}
 
-		for element in &self.windows {
+		// tiled windows of a scrolled layout are cut off where the layout area ends, and left
+		// out if they are entirely outside of it
+		let local_zone = layer_map.non_exclusive_zone();
+		let zone = Rectangle::from_loc_and_size(local_zone.loc + offset, local_zone.size);
+		for element in windows {
 			let window = &element.borrow().smithay_window;
-			// if CONFIG.read().decorations.border.width > 0 {
-			if 3 > 0 {
+			let rec = element.borrow().rec;
+			let tiled = !element.borrow().floating;
+			if tiled && !zone.overlaps(rec) {
+				continue;
+			}
+			if border.enable && border.width > 0 {
 				render_elements.push(CustomRenderElements::Shader(BorderShader::element(
 					renderer.glow_renderer_mut(),
 					window,
-					element.borrow().rec.loc,
+					rec.loc - offset,
+					border,
+					focus == Some(window),
 				)));
 			}
-			render_elements.append(&mut window.render_elements(
-				renderer,
-				element.borrow().render_location().to_physical(1),
-				Scale::from(1.0),
-				1.0,
-			));
+			let location = (element.borrow().render_location() - offset).to_physical(1);
+			if tiled && !zone.contains_rect(rec) {
+				render_elements.extend(
+					window
+						.render_elements::<WaylandSurfaceRenderElement<R>>(
+							renderer,
+							location,
+							Scale::from(1.0),
+							1.0,
+						)
+						.into_iter()
+						.filter_map(|e| {
+							CropRenderElement::from_element(
+								e,
+								Scale::from(1.0),
+								local_zone.to_physical(1),
+							)
+						})
+						.map(CustomRenderElements::Cropped),
+				);
+			} else {
+				render_elements.append(&mut window.render_elements(
+					renderer,
+					location,
+					Scale::from(1.0),
+					1.0,
+				));
+			}
 		}
 
-		render_elements.extend(
-			lower
-				.into_iter()
-				.filter_map(|surface| {
-					layer_map.layer_geometry(surface).map(|geo| (geo.loc, surface))
-				})
-				.flat_map(|(loc, surface)| {
-					AsRenderElements::<R>::render_elements::<CustomRenderElements<_>>(
-						surface,
-						renderer,
-						loc.to_physical_precise_round(1),
-						Scale::from(1.0),
-						1.0,
-					)
-				}),
-		);
+		render_elements.extend(layer_elements(renderer, &[Layer::Bottom, Layer::Background]));
 		render_elements
 	}
 
-	pub fn outputs(&self) -> impl Iterator<Item = &Output> {
-		self.outputs.iter()
+	pub fn output(&self) -> Option<&Output> {
+		self.output.as_ref()
 	}
 
-	pub fn add_output(&mut self, output: Output) {
-		self.outputs.push(output);
+	/// Binds the workspace to `output`, taking its floating windows along to the same place
+	/// on the new output.
+	fn set_output(&mut self, output: Option<Output>) {
+		let from = self.output_geometry();
+		self.output = output;
+		if let (Some(from), Some(to)) = (from, self.output_geometry()) {
+			self.shift_floating(to.loc - from.loc);
+		}
 	}
 
-	pub fn remove_output(&mut self, output: &Output) {
-		self.outputs.retain(|o| o != output);
+	/// Moves the floating windows by `offset`, for when their output moves.
+	fn shift_floating(&mut self, offset: Point<i32, Logical>) {
+		for window in self.windows.iter() {
+			window.borrow_mut().shift(offset);
+		}
 	}
 
-	pub fn output_geometry(&self, o: &Output) -> Option<Rectangle<i32, Logical>> {
-		if !self.outputs.contains(o) {
-			return None;
-		}
+	/// The geometry of the output of the workspace, in the global space.
+	pub fn output_geometry(&self) -> Option<Rectangle<i32, Logical>> {
+		self.output.as_ref().and_then(output_geometry)
+	}
 
-		let transform: Transform = o.current_transform();
-		o.current_mode().map(|mode| {
-			Rectangle::from_loc_and_size(
-				(0, 0),
-				transform
-					.transform_size(mode.size)
-					.to_f64()
-					.to_logical(o.current_scale().fractional_scale())
-					.to_i32_ceil(),
-			)
-		})
+	/// The part of the output not taken by layer surfaces, in the global space.
+	pub fn zone(&self) -> Option<Rectangle<i32, Logical>> {
+		let output = self.output.as_ref()?;
+		let mut zone = layer_map_for_output(output).non_exclusive_zone();
+		zone.loc += output.current_location();
+		Some(zone)
 	}
 
 	pub fn window_under<P: Into<Point<f64, Logical>>>(
 		&self,
 		point: P,
 	) -> Option<(Ref<'_, Window>, Point<i32, Logical>)> {
-		let point = point.into();
-		self.windows.iter().filter(|e| e.borrow().bbox().to_f64().contains(point)).find_map(|e| {
+		Self::window_under_among(self.stacked_windows(), point.into())
+	}
+
+	pub fn fullscreen_window_under<P: Into<Point<f64, Logical>>>(
+		&self,
+		point: P,
+	) -> Option<(Ref<'_, Window>, Point<i32, Logical>)> {
+		Self::window_under_among(
+			self.stacked_windows().filter(|w| w.borrow().fullscreen),
+			point.into(),
+		)
+	}
+
+	fn window_under_among<'a>(
+		windows: impl Iterator<Item = &'a Rc<RefCell<StrataWindow>>>,
+		point: Point<f64, Logical>,
+	) -> Option<(Ref<'a, Window>, Point<i32, Logical>)> {
+		windows.filter(|e| e.borrow().bbox().to_f64().contains(point)).find_map(|e| {
 			// we need to offset the point to the location where the surface is actually drawn
 			let render_location = e.borrow().render_location();
 			if e.borrow().smithay_window.is_in_input_region(&(point - render_location.to_f64())) {
@@ -257,6 +1012,21 @@ impl Workspace {
 	}
 }
 
+/// Where `output` is in the global space, in which outputs are side by side.
+pub fn output_geometry(output: &Output) -> Option<Rectangle<i32, Logical>> {
+	let transform: Transform = output.current_transform();
+	output.current_mode().map(|mode| {
+		Rectangle::from_loc_and_size(
+			output.current_location(),
+			transform
+				.transform_size(mode.size)
+				.to_f64()
+				.to_logical(output.current_scale().fractional_scale())
+				.to_i32_ceil(),
+		)
+	})
+}
+
 impl Default for Workspace {
 	fn default() -> Self {
 		Self::new()
@@ -268,17 +1038,142 @@ impl Workspaces {
 		Workspaces {
 			workspaces: (0..workspaceamount).map(|_| Workspace::new()).collect(),
 			current: 0,
+			outputs: Vec::new(),
+			disconnected: Vec::new(),
 		}
 	}
 
+	/// The connected outputs, from left to right.
 	pub fn outputs(&self) -> impl Iterator<Item = &Output> {
-		self.workspaces.iter().flat_map(|w| w.outputs())
+		self.outputs.iter().map(|(output, _)| output)
+	}
+
+	/// The output the current workspace is shown on.
+	pub fn current_output(&self) -> Option<&Output> {
+		self.current().output().filter(|output| self.outputs().any(|o| o == *output))
+	}
+
+	pub fn output_at(&self, pos: Point<f64, Logical>) -> Option<&Output> {
+		self.outputs().find(|o| output_geometry(o).is_some_and(|g| g.to_f64().contains(pos)))
+	}
+
+	/// The output `name` refers to: one by its name, or the one left or right of the current
+	/// output.
+	pub fn find_output(&self, name: &str) -> Option<&Output> {
+		let index = self.outputs().position(|o| Some(o) == self.current_output());
+		match (name, index) {
+			("left", Some(index)) => self.outputs().nth(index.checked_sub(1)?),
+			("right", Some(index)) => self.outputs().nth(index + 1),
+			(name, _) => self.outputs().find(|o| o.name() == name),
+		}
+	}
+
+	fn shown_on(&self, output: &Output) -> Option<u8> {
+		self.outputs.iter().find(|(o, _)| o == output).and_then(|(_, shown)| *shown)
+	}
+
+	fn is_shown(&self, id: u8) -> bool {
+		self.outputs.iter().any(|(_, shown)| *shown == Some(id))
+	}
+
+	/// The workspace shown on `output`.
+	pub fn on_output(&self, output: &Output) -> Option<&Workspace> {
+		self.shown_on(output).map(|id| &self.workspaces[id as usize])
+	}
+
+	/// Puts `output` to the right of the other outputs. Workspaces that were on it before it
+	/// was disconnected come back to it, and it shows the workspace it showed then. Otherwise
+	/// the first output gets every workspace, the others the first one that isn't shown yet.
+	pub fn add_output(&mut self, output: Output) {
+		let name = output.name();
+		let restored = match self.disconnected.iter().position(|(n, _)| *n == name) {
+			Some(index) => self.disconnected.remove(index).1,
+			None => None,
+		};
+
+		let returning: Vec<u8> = (0..self.workspaces.len() as u8)
+			.filter(|&id| self.workspaces[id as usize].home.as_deref() == Some(name.as_str()))
+			.collect();
+		for &id in returning.iter() {
+			let workspace = &mut self.workspaces[id as usize];
+			workspace.home = None;
+			workspace.set_output(Some(output.clone()));
+		}
+		for id in returning {
+			self.hide(id);
+		}
+		for workspace in self.workspaces.iter_mut().filter(|w| w.output.is_none()) {
+			workspace.set_output(Some(output.clone()));
+		}
+
+		let ids = restored.into_iter().chain(std::iter::once(self.current));
+		let shown = ids
+			.clone()
+			.chain(0..self.workspaces.len() as u8)
+			.find(|&id| {
+				!self.is_shown(id) && self.workspaces[id as usize].output() == Some(&output)
+			})
+			.or_else(|| ids.chain(0..self.workspaces.len() as u8).find(|&id| !self.is_shown(id)));
+		if let Some(id) = shown.filter(|&id| self.workspaces[id as usize].output() != Some(&output))
+		{
+			self.workspaces[id as usize].set_output(Some(output.clone()));
+		}
+		if self.outputs.is_empty() {
+			self.current = shown.unwrap_or(self.current);
+		}
+		self.outputs.push((output, shown));
+		self.arrange_outputs();
+	}
+
+	/// Moves the workspaces of `output` to the leftmost remaining output, where they stay
+	/// until `output` comes back. If the current workspace was shown on `output`, it is shown
+	/// there instead.
+	pub fn remove_output(&mut self, output: &Output) {
+		let Some(index) = self.outputs.iter().position(|(o, _)| o == output) else {
+			return;
+		};
+		let (_, shown) = self.outputs.remove(index);
+		let name = output.name();
+		self.disconnected.retain(|(n, _)| *n != name);
+		self.disconnected.push((name.clone(), shown));
+
+		let fallback = self.outputs().next().cloned();
+		for workspace in self.workspaces.iter_mut().filter(|w| w.output() == Some(output)) {
+			workspace.home.get_or_insert_with(|| name.clone());
+			workspace.set_output(fallback.clone());
+		}
+		if let Some(fallback) = fallback.filter(|_| shown == Some(self.current)) {
+			self.show(self.current, &fallback);
+		}
+		self.arrange_outputs();
+	}
+
+	/// Puts the outputs side by side from left to right, in the order they were connected,
+	/// and lays out the workspaces on them again. Called whenever an output is added, removed
+	/// or changes size.
+	pub fn arrange_outputs(&mut self) {
+		let mut x = 0;
+		for (output, _) in self.outputs.iter() {
+			let offset = Point::from((x, 0)) - output.current_location();
+			output.change_current_state(None, None, None, Some((x, 0).into()));
+			for workspace in self.workspaces.iter_mut().filter(|w| w.output() == Some(output)) {
+				workspace.shift_floating(offset);
+			}
+			x += output_geometry(output).map_or(0, |geometry| geometry.size.w);
+		}
+		for workspace in self.workspaces.iter_mut().filter(|w| w.output.is_some()) {
+			refresh_geometry(workspace);
+		}
 	}
 
 	pub fn iter(&mut self) -> impl Iterator<Item = &mut Workspace> {
 		self.workspaces.iter_mut()
 	}
 
+	pub fn len(&self) -> usize {
+		self.workspaces.len()
+	}
+
 	pub fn current_mut(&mut self) -> &mut Workspace {
 		&mut self.workspaces[self.current as usize]
 	}
@@ -291,23 +1186,169 @@ impl Workspaces {
 		self.workspaces.iter().flat_map(|w| w.windows())
 	}
 
+	pub fn find_window(&self, toplevel: &ToplevelSurface) -> Option<Window> {
+		self.all_windows().find(|w| w.toplevel() == toplevel).map(|w| w.clone())
+	}
+
 	pub fn workspace_from_window(&mut self, window: &Window) -> Option<&mut Workspace> {
 		self.workspaces.iter_mut().find(|w| w.contains_window(window))
 	}
 
+	/// Applies `gaps` to every workspace, replacing gaps set on single workspaces.
+	pub fn set_gaps(&mut self, gaps: Gaps) {
+		for workspace in self.workspaces.iter_mut() {
+			workspace.gaps = gaps;
+			refresh_geometry(workspace);
+		}
+	}
+
+	/// Gives every workspace the gaps of `general` and the layout of `tiling`, replacing the
+	/// ones set on single workspaces.
+	pub fn apply_config(&mut self, general: &General, tiling: &Tiling) {
+		self.set_gaps(Gaps::from(general));
+		self.set_tiling(tiling);
+	}
+
+	/// Applies the layout from `tiling` to every workspace, replacing layouts set on single
+	/// workspaces.
+	pub fn set_tiling(&mut self, tiling: &Tiling) {
+		for workspace in self.workspaces.iter_mut() {
+			workspace.set_layout(tiling.layout.clone(), tiling);
+			workspace.layout.configure(tiling);
+			refresh_geometry(workspace);
+		}
+	}
+
+	/// Makes workspace `id` the current one, showing it on its output. Workspaces without an
+	/// output go to the current one.
 	pub fn activate(&mut self, id: u8) {
+		let output = match self.workspaces[id as usize].output.clone() {
+			Some(output) => Some(output),
+			None => self.current_output().cloned(),
+		};
+		if let Some(output) = output {
+			self.show(id, &output);
+		}
 		self.current = id;
 	}
 
+	/// Shows workspace `id` on `output`, binding it to `output`. The output it was shown on
+	/// before shows another of its workspaces, if it has one that isn't shown.
+	fn show(&mut self, id: u8, output: &Output) {
+		if self.workspaces[id as usize].output() != Some(output) {
+			self.workspaces[id as usize].set_output(Some(output.clone()));
+			self.hide(id);
+			refresh_geometry(&mut self.workspaces[id as usize]);
+		}
+		if let Some((_, shown)) = self.outputs.iter_mut().find(|(o, _)| o == output) {
+			*shown = Some(id);
+		}
+	}
+
+	/// Takes workspace `id` off the output showing it, which shows another of its workspaces
+	/// instead, if it has one that isn't shown.
+	fn hide(&mut self, id: u8) {
+		let Some(index) = self.outputs.iter().position(|(_, shown)| *shown == Some(id)) else {
+			return;
+		};
+		let output = &self.outputs[index].0;
+		let next = (0..self.workspaces.len() as u8).find(|&other| {
+			other != id
+				&& !self.is_shown(other)
+				&& self.workspaces[other as usize].output() == Some(output)
+		});
+		self.outputs[index].1 = next;
+	}
+
+	/// Makes the workspace shown on `output` the current one.
+	pub fn focus_output(&mut self, output: &Output) {
+		if let Some(id) = self.shown_on(output) {
+			self.current = id;
+		}
+	}
+
+	/// Moves workspace `id` to `output` and shows it there.
+	pub fn move_workspace_to_output(&mut self, id: u8, output: &Output) {
+		self.show(id, output);
+		self.current = id;
+	}
+
+	/// Moves `window` to the workspace shown on `output`.
+	pub fn move_window_to_output(&mut self, window: &Window, output: &Output) {
+		if let Some(id) = self.shown_on(output) {
+			self.move_window_to_workspace(window, id);
+		}
+	}
+
+	/// Keeps the pointer on the outputs. Moving it off their outer edges, it stays on the
+	/// output it was on at `from`.
+	pub fn clamp_coords(
+		&self,
+		pos: Point<f64, Logical>,
+		from: Point<f64, Logical>,
+	) -> Point<f64, Logical> {
+		if self.output_at(pos).is_some() {
+			return pos;
+		}
+		let Some(geometry) =
+			self.output_at(from).or(self.outputs().next()).and_then(output_geometry)
+		else {
+			return pos;
+		};
+
+		let (min_x, min_y) = geometry.loc.to_f64().into();
+		let max_x = (geometry.loc.x + geometry.size.w - 1) as f64;
+		let max_y = (geometry.loc.y + geometry.size.h - 1) as f64;
+		(pos.x.max(min_x).min(max_x), pos.y.max(min_y).min(max_y)).into()
+	}
+
+	/// Floating windows keep their place relative to the output when the workspace is on
+	/// another one.
 	pub fn move_window_to_workspace(&mut self, window: &Window, workspace: u8) {
 		let mut removed = None;
+		let mut from = None;
 		if let Some(ws) = self.workspace_from_window(window) {
+			from = ws.output_geometry();
 			removed = ws.remove_window(window);
 			refresh_geometry(ws)
 		}
 		if let Some(removed) = removed {
-			self.workspaces[workspace as usize].add_window(removed);
-			refresh_geometry(&mut self.workspaces[workspace as usize])
+			let target = &mut self.workspaces[workspace as usize];
+			if let (Some(from), Some(to)) = (from, target.output_geometry()) {
+				removed.borrow_mut().shift(to.loc - from.loc);
+			}
+			target.add_window(removed);
+			target.record_focus(window);
+			refresh_geometry(target)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		Gaps,
+		Workspaces,
+	};
+	use crate::config::{
+		General,
+		LayoutKind,
+		Tiling,
+	};
+
+	#[test]
+	fn applying_a_config_replaces_the_gaps_and_layouts() {
+		let mut workspaces = Workspaces::new(3);
+		workspaces.apply_config(&General::default(), &Tiling::default());
+		workspaces.workspaces[1].gaps = Gaps { inner: 1, outer: 2, smart: false };
+
+		let general = General { gaps_in: 7, gaps_out: 20, smart_gaps: true, ..General::default() };
+		let tiling = Tiling { layout: LayoutKind::Grid, ..Tiling::default() };
+		workspaces.apply_config(&general, &tiling);
+
+		for workspace in workspaces.iter() {
+			assert_eq!(workspace.gaps, Gaps { inner: 7, outer: 20, smart: true });
+			assert_eq!(workspace.layout.kind(), LayoutKind::Grid);
 		}
 	}
 }
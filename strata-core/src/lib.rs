@@ -1,20 +1,31 @@
-use mlua::{
-	FromLua,
-	Lua,
-	Result,
+use piccolo::{
+	Context,
+	Table,
+	TypeError,
 	Value,
 };
 
-pub trait UpdateFromLua<'lua>: FromLua<'lua> {
-	fn update_from_lua(&mut self, value: Value<'lua>, lua: &'lua Lua) -> Result<()>;
+/// Builds a value out of a Lua value. Implemented by `#[derive(Config)]` for config structs.
+pub trait FromLua<'gc>: Sized {
+	fn from_lua(ctx: Context<'gc>, value: Value<'gc>) -> Result<Self, TypeError>;
+}
+
+/// Overwrites only the parts of `self` that are present in the Lua value.
+pub trait UpdateFromLua<'gc>: FromLua<'gc> {
+	fn update_from_lua(&mut self, ctx: Context<'gc>, value: Value<'gc>) -> Result<(), TypeError>;
+}
+
+/// Converts a value back into Lua, the inverse of [`FromLua`].
+pub trait ToLua {
+	fn to_lua<'gc>(&self, ctx: Context<'gc>) -> Value<'gc>;
 }
 
 macro_rules! impl_update_from_lua {
 	($($ty:ty),*) => {
 		$(
-			impl<'lua> UpdateFromLua<'lua> for $ty {
-				fn update_from_lua(&mut self, value: Value<'lua>, _: &'lua Lua) -> Result<()> {
-					*self = Self::from_lua(value, &Lua::new())?;
+			impl<'gc> UpdateFromLua<'gc> for $ty {
+				fn update_from_lua(&mut self, ctx: Context<'gc>, value: Value<'gc>) -> Result<(), TypeError> {
+					*self = Self::from_lua(ctx, value)?;
 					Ok(())
 				}
 			}
@@ -22,18 +33,140 @@ macro_rules! impl_update_from_lua {
 	};
 }
 
+macro_rules! impl_integer {
+	($($ty:ty),*) => {
+		$(
+			impl<'gc> FromLua<'gc> for $ty {
+				fn from_lua(_: Context<'gc>, value: Value<'gc>) -> Result<Self, TypeError> {
+					let int = match value {
+						Value::Integer(i) => Some(i),
+						Value::Number(n) if n.fract() == 0.0 => Some(n as i64),
+						_ => None,
+					};
+
+					int.and_then(|i| <$ty>::try_from(i).ok())
+						.ok_or(TypeError { expected: stringify!($ty), found: value.type_name() })
+				}
+			}
+
+			impl ToLua for $ty {
+				fn to_lua<'gc>(&self, _: Context<'gc>) -> Value<'gc> {
+					Value::Integer(*self as i64)
+				}
+			}
+		)*
+	};
+}
+
+macro_rules! impl_number {
+	($($ty:ty),*) => {
+		$(
+			impl<'gc> FromLua<'gc> for $ty {
+				fn from_lua(_: Context<'gc>, value: Value<'gc>) -> Result<Self, TypeError> {
+					match value {
+						Value::Integer(i) => Ok(i as $ty),
+						Value::Number(n) => Ok(n as $ty),
+						_ => Err(TypeError { expected: stringify!($ty), found: value.type_name() }),
+					}
+				}
+			}
+
+			impl ToLua for $ty {
+				fn to_lua<'gc>(&self, _: Context<'gc>) -> Value<'gc> {
+					Value::Number(*self as f64)
+				}
+			}
+		)*
+	};
+}
+
+impl_integer!(i8, i16, i32, i64, u8, u16, u32, u64);
+impl_number!(f32, f64);
 impl_update_from_lua!(bool, i8, i16, i32, i64, u8, u16, u32, u64, f32, f64, String);
 
-impl<'lua, T: FromLua<'lua>> UpdateFromLua<'lua> for Vec<T> {
-	fn update_from_lua(&mut self, value: Value<'lua>, lua: &'lua Lua) -> Result<()> {
-		*self = FromLua::from_lua(value, lua)?;
+impl<'gc> FromLua<'gc> for bool {
+	fn from_lua(_: Context<'gc>, value: Value<'gc>) -> Result<Self, TypeError> {
+		match value {
+			Value::Boolean(b) => Ok(b),
+			_ => Err(TypeError { expected: "boolean", found: value.type_name() }),
+		}
+	}
+}
+
+impl ToLua for bool {
+	fn to_lua<'gc>(&self, _: Context<'gc>) -> Value<'gc> {
+		Value::Boolean(*self)
+	}
+}
+
+impl<'gc> FromLua<'gc> for String {
+	fn from_lua(_: Context<'gc>, value: Value<'gc>) -> Result<Self, TypeError> {
+		match value {
+			Value::String(s) => s
+				.to_str()
+				.map(str::to_owned)
+				.map_err(|_| TypeError { expected: "utf-8 string", found: "string" }),
+			_ => Err(TypeError { expected: "string", found: value.type_name() }),
+		}
+	}
+}
+
+impl ToLua for String {
+	fn to_lua<'gc>(&self, ctx: Context<'gc>) -> Value<'gc> {
+		Value::String(ctx.intern(self.as_bytes()))
+	}
+}
+
+impl<'gc, T: FromLua<'gc>> FromLua<'gc> for Vec<T> {
+	fn from_lua(ctx: Context<'gc>, value: Value<'gc>) -> Result<Self, TypeError> {
+		match value {
+			Value::Table(table) => {
+				(1..=table.length()).map(|i| T::from_lua(ctx, table.get(ctx, i))).collect()
+			}
+			_ => Err(TypeError { expected: "table", found: value.type_name() }),
+		}
+	}
+}
+
+impl<'gc, T: FromLua<'gc>> UpdateFromLua<'gc> for Vec<T> {
+	fn update_from_lua(&mut self, ctx: Context<'gc>, value: Value<'gc>) -> Result<(), TypeError> {
+		*self = Self::from_lua(ctx, value)?;
 		Ok(())
 	}
 }
 
-impl<'lua, T: FromLua<'lua>> UpdateFromLua<'lua> for Option<T> {
-	fn update_from_lua(&mut self, value: Value<'lua>, lua: &'lua Lua) -> Result<()> {
-		*self = FromLua::from_lua(value, lua)?;
+impl<T: ToLua> ToLua for Vec<T> {
+	fn to_lua<'gc>(&self, ctx: Context<'gc>) -> Value<'gc> {
+		let table = Table::new(&ctx);
+		for (i, item) in self.iter().enumerate() {
+			table.set(ctx, i as i64 + 1, item.to_lua(ctx)).expect("integer keys are always valid");
+		}
+
+		Value::Table(table)
+	}
+}
+
+impl<'gc, T: FromLua<'gc>> FromLua<'gc> for Option<T> {
+	fn from_lua(ctx: Context<'gc>, value: Value<'gc>) -> Result<Self, TypeError> {
+		match value {
+			Value::Nil => Ok(None),
+			value => T::from_lua(ctx, value).map(Some),
+		}
+	}
+}
+
+impl<'gc, T: FromLua<'gc>> UpdateFromLua<'gc> for Option<T> {
+	fn update_from_lua(&mut self, ctx: Context<'gc>, value: Value<'gc>) -> Result<(), TypeError> {
+		*self = Self::from_lua(ctx, value)?;
 		Ok(())
 	}
 }
+
+impl<T: ToLua> ToLua for Option<T> {
+	fn to_lua<'gc>(&self, ctx: Context<'gc>) -> Value<'gc> {
+		match self {
+			Some(value) => value.to_lua(ctx),
+			None => Value::Nil,
+		}
+	}
+}
@@ -36,7 +36,11 @@ where
 	}
 }
 
-/// Procedural macro to derive a wrapper with optional fields around a struct.
+/// Derives `strata_core::{FromLua, UpdateFromLua, ToLua}` and `piccolo::FromValue`.
+///
+/// Fields are optional unless marked `#[config(flat)]`; `#[config(from = T)]` parses the field
+/// through `T` and converts it with `Into`. Enums are parsed from strings with `FromStr` and
+/// written back with `AsRef<str>`.
 #[proc_macro_derive(Config, attributes(config))]
 pub fn config_derive(input: TokenStream) -> TokenStream {
 	let input = syn::parse_macro_input!(input as syn::DeriveInput);
@@ -51,94 +55,165 @@ pub fn config_derive(input: TokenStream) -> TokenStream {
 				(field.ident.as_ref().unwrap(), &field.ty, attrs)
 			});
 
-			let from_lua_fields = fields.clone().map(|(field_name, field_type, attrs)| {
-				let field_type = attrs.from_type.as_ref().unwrap_or(field_type);
-				let field_value = if attrs.is_flat {
-					quote! {
-						table.get::<_, #field_type>(stringify!(#field_name))?.into()
-					}
-				} else {
+			let has_flat_fields = fields.clone().any(|(_, _, attrs)| attrs.is_flat);
+
+			// structs without required fields start from their own `Default`, so keys missing
+			// from the table keep the struct's defaults instead of the field type's
+			let from_lua_body = if has_flat_fields {
+				let from_lua_fields = fields.clone().map(|(field_name, field_type, attrs)| {
+					let from_type = attrs.from_type.as_ref().unwrap_or(field_type);
+					let field_value = if attrs.is_flat {
+						quote! {
+							<#from_type as strata_core::FromLua>::from_lua(
+								ctx,
+								table.get(ctx, stringify!(#field_name)),
+							)?
+							.into()
+						}
+					} else {
+						quote! {
+							match table.get(ctx, stringify!(#field_name)) {
+								piccolo::Value::Nil => Default::default(),
+								value => <#from_type as strata_core::FromLua>::from_lua(ctx, value)?.into(),
+							}
+						}
+					};
+
 					quote! {
-						table
-							.get::<_, Option<#field_type>>(stringify!(#field_name))?
-							.map(Into::into)
-							.unwrap_or_default()
+						#field_name: #field_value
 					}
-				};
+				});
 
 				quote! {
-					#field_name: #field_value
+					let table = match value {
+						piccolo::Value::Table(table) => table,
+						_ => return Err(piccolo::TypeError { expected: "table", found: value.type_name() }),
+					};
+					Ok(Self {
+						#(#from_lua_fields),*
+					})
 				}
-			});
+			} else {
+				quote! {
+					let mut this = Self::default();
+					strata_core::UpdateFromLua::update_from_lua(&mut this, ctx, value)?;
+					Ok(this)
+				}
+			};
 
-			let update_from_lua_fields = fields.map(|(field_name, _field_type, attrs)| {
+			let update_from_lua_fields = fields.clone().map(|(field_name, _field_type, attrs)| {
 				match (attrs.is_flat, attrs.from_type) {
 					(true, None) => {
 						quote! {
-							self.#field_name.update_from_lua(table.get(stringify!(#field_name))?, lua)?;
+							strata_core::UpdateFromLua::update_from_lua(
+								&mut self.#field_name,
+								ctx,
+								table.get(ctx, stringify!(#field_name)),
+							)?;
 						}
 					}
 					(true, Some(from_type)) => {
 						quote! {
-							self.#field_name = table.get::<_, #from_type>(stringify!(#field_name))?.into();
+							self.#field_name = <#from_type as strata_core::FromLua>::from_lua(
+								ctx,
+								table.get(ctx, stringify!(#field_name)),
+							)?
+							.into();
 						}
 					}
 					(false, None) => {
 						quote! {
-							if let Some(value) = table.get::<_, Option<mlua::Value>>(stringify!(#field_name))? {
-								self.#field_name.update_from_lua(value, lua)?;
+							match table.get(ctx, stringify!(#field_name)) {
+								piccolo::Value::Nil => {}
+								value => strata_core::UpdateFromLua::update_from_lua(&mut self.#field_name, ctx, value)?,
 							}
 						}
 					}
 					(false, Some(from_type)) => {
 						quote! {
-							if let Some(value) = table.get::<_, Option<#from_type>>(stringify!(#field_name))? {
-								self.#field_name = value.into();
+							match table.get(ctx, stringify!(#field_name)) {
+								piccolo::Value::Nil => {}
+								value => {
+									self.#field_name = <#from_type as strata_core::FromLua>::from_lua(ctx, value)?.into();
+								}
 							}
 						}
 					}
 				}
 			});
 
+			let to_lua_fields = fields.map(|(field_name, _, _)| {
+				quote! {
+					table
+						.set(ctx, stringify!(#field_name), strata_core::ToLua::to_lua(&self.#field_name, ctx))
+						.expect("string keys are always valid");
+				}
+			});
+
 			quote! {
-				impl<'lua> mlua::FromLua<'lua> for #struct_name {
-					fn from_lua(value: mlua::Value<'lua>, lua: &'lua mlua::Lua) -> mlua::Result<Self> {
-						let table = mlua::Table::from_lua(value, lua)?;
-						Ok(Self {
-							#(#from_lua_fields),*
-						})
+				impl<'gc> strata_core::FromLua<'gc> for #struct_name {
+					fn from_lua(ctx: piccolo::Context<'gc>, value: piccolo::Value<'gc>) -> Result<Self, piccolo::TypeError> {
+						#from_lua_body
 					}
 				}
 
-				impl<'lua> strata_core::UpdateFromLua<'lua> for #struct_name {
-					fn update_from_lua(&mut self, value: mlua::Value<'lua>, lua: &'lua mlua::Lua) -> mlua::Result<()> {
-						let table = mlua::Table::from_lua(value, lua)?;
+				impl<'gc> strata_core::UpdateFromLua<'gc> for #struct_name {
+					fn update_from_lua(&mut self, ctx: piccolo::Context<'gc>, value: piccolo::Value<'gc>) -> Result<(), piccolo::TypeError> {
+						let table = match value {
+							piccolo::Value::Table(table) => table,
+							_ => return Err(piccolo::TypeError { expected: "table", found: value.type_name() }),
+						};
 						#(#update_from_lua_fields)*
 						Ok(())
 					}
 				}
+
+				impl strata_core::ToLua for #struct_name {
+					fn to_lua<'gc>(&self, ctx: piccolo::Context<'gc>) -> piccolo::Value<'gc> {
+						let table = piccolo::Table::new(&ctx);
+						#(#to_lua_fields)*
+						piccolo::Value::Table(table)
+					}
+				}
+
+				impl<'gc> piccolo::FromValue<'gc> for #struct_name {
+					fn from_value(ctx: piccolo::Context<'gc>, value: piccolo::Value<'gc>) -> Result<Self, piccolo::TypeError> {
+						strata_core::FromLua::from_lua(ctx, value)
+					}
+				}
 			}
 		}
 		syn::Data::Enum(_) => {
 			let enum_name = input.ident;
 			quote! {
-				impl<'lua> mlua::FromLua<'lua> for #enum_name {
-					fn from_lua(value: mlua::Value<'lua>, lua: &'lua mlua::Lua) -> mlua::Result<Self> {
-						let str = String::from_lua(value, lua)?;
-						 std::str::FromStr::from_str(&str).map_err(|_| mlua::Error::FromLuaConversionError {
-							from: "string",
-							to: stringify!(#enum_name),
-							message: Some("invalid variant".to_owned()),
+				impl<'gc> strata_core::FromLua<'gc> for #enum_name {
+					fn from_lua(ctx: piccolo::Context<'gc>, value: piccolo::Value<'gc>) -> Result<Self, piccolo::TypeError> {
+						let str = <String as strata_core::FromLua>::from_lua(ctx, value)?;
+						std::str::FromStr::from_str(&str).map_err(|_| piccolo::TypeError {
+							expected: stringify!(#enum_name),
+							found: "invalid variant",
 						})
 					}
 				}
 
-				impl<'lua> strata_core::UpdateFromLua<'lua> for #enum_name {
-					fn update_from_lua(&mut self, value: mlua::Value<'lua>, lua: &'lua mlua::Lua) -> mlua::Result<()> {
-						*self = Self::from_lua(value, lua)?;
+				impl<'gc> strata_core::UpdateFromLua<'gc> for #enum_name {
+					fn update_from_lua(&mut self, ctx: piccolo::Context<'gc>, value: piccolo::Value<'gc>) -> Result<(), piccolo::TypeError> {
+						*self = <Self as strata_core::FromLua>::from_lua(ctx, value)?;
 						Ok(())
 					}
 				}
+
+				impl strata_core::ToLua for #enum_name {
+					fn to_lua<'gc>(&self, ctx: piccolo::Context<'gc>) -> piccolo::Value<'gc> {
+						piccolo::Value::String(ctx.intern(AsRef::<str>::as_ref(self).as_bytes()))
+					}
+				}
+
+				impl<'gc> piccolo::FromValue<'gc> for #enum_name {
+					fn from_value(ctx: piccolo::Context<'gc>, value: piccolo::Value<'gc>) -> Result<Self, piccolo::TypeError> {
+						strata_core::FromLua::from_lua(ctx, value)
+					}
+				}
 			}
 		}
 		_ => {
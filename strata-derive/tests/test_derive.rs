@@ -1,8 +1,12 @@
-use mlua::{
-	FromLua,
+use piccolo::{
 	Lua,
+	Table,
 	Value,
 };
+use strata_core::{
+	FromLua,
+	UpdateFromLua,
+};
 use strata_derive::Config;
 
 #[derive(Debug, PartialEq, Default, Config)]
@@ -14,11 +18,28 @@ struct Foo {
 
 #[test]
 fn test_config() {
-	let lua = Lua::new();
-	let table = lua.create_table().unwrap();
-	table.set("b", "hello").unwrap();
+	let mut lua = Lua::core();
+	lua.enter(|ctx| {
+		let table = Table::new(&ctx);
+		table.set(ctx, "b", "hello").unwrap();
+
+		let foo = Foo::from_lua(ctx, Value::Table(table)).unwrap();
+
+		assert_eq!(foo, Foo { a: 0, b: "hello".to_string() });
+	});
+}
+
+#[test]
+fn test_update_config() {
+	let mut lua = Lua::core();
+	lua.enter(|ctx| {
+		let mut foo = Foo { a: 1, b: "hello".to_string() };
 
-	let foo: Foo = FromLua::from_lua(Value::Table(table), &lua).unwrap();
+		let table = Table::new(&ctx);
+		table.set(ctx, "a", 2i64).unwrap();
+		table.set(ctx, "b", "world").unwrap();
+		foo.update_from_lua(ctx, Value::Table(table)).unwrap();
 
-	assert_eq!(foo, Foo { a: 0, b: "hello".to_string() });
+		assert_eq!(foo, Foo { a: 2, b: "world".to_string() });
+	});
 }
@@ -6,6 +6,16 @@ local function close_all_windows()
 	end
 end
 
+-- Puts every window in a column of its own. Select it with `strata.set_layout("columns")`.
+strata.register_layout("columns", function(area, windows, state)
+	local recs = {}
+	local width = area.width // math.max(#windows, 1)
+	for i = 1, #windows do
+		recs[i] = { x = area.x + (i - 1) * width, y = area.y, width = width, height = area.height }
+	end
+	return recs
+end)
+
 local config = {
 	autostart = {
 		{ "kitty --title Terminal" },
@@ -14,6 +24,7 @@ local config = {
 		workspaces = 9,
 		gaps_in = 8,
 		gaps_out = 12,
+		smart_gaps = false,
 		kb_repeat = { 500, 250 },
 	},
 	decorations = {
@@ -23,6 +34,10 @@ local config = {
 			active = "#FFF",
 			inactive = "#131418",
 			radius = 5,
+			gradient = {
+				active = "#8FBCBB",
+				angle = 45,
+			},
 		},
 		window = {
 			opacity = 0.9,
@@ -42,6 +57,27 @@ local config = {
 	},
 	tiling = {
 		layout = "dwindle",
+		master = {
+			count = 1,
+			ratio = 0.55,
+			side = "left",
+		},
+		monocle = {
+			tab_bar = true,
+			tab_height = 24,
+		},
+		scrolling = {
+			widths = { 0.5, 0.667, 1.0 },
+		},
+	},
+	mouse = {
+		modifier = "Super_L",
+		move_button = "left",
+		resize_button = "right",
+	},
+	focus = {
+		follow_mouse = true,
+		warp_pointer = true,
 	},
 	animations = {
 		enable = true,
@@ -63,6 +99,154 @@ local config = {
 			keys = { "Alt_L", "m" },
 			action = strata.actions.quit,
 		},
+		{
+			keys = { "Super_L", "f" },
+			action = strata.window.toggle_floating(),
+		},
+		{
+			keys = { "Super_L", "Shift_L", "f" },
+			action = strata.window.toggle_fullscreen(),
+		},
+		{
+			keys = { "Super_L", "m" },
+			action = strata.window.toggle_maximized(),
+		},
+		{
+			keys = { "Super_L", "h" },
+			action = strata.window.focus("left"),
+		},
+		{
+			keys = { "Super_L", "j" },
+			action = strata.window.focus("down"),
+		},
+		{
+			keys = { "Super_L", "k" },
+			action = strata.window.focus("up"),
+		},
+		{
+			keys = { "Super_L", "l" },
+			action = strata.window.focus("right"),
+		},
+		{
+			keys = { "Super_L", "Shift_L", "h" },
+			action = strata.window.swap("left"),
+		},
+		{
+			keys = { "Super_L", "Shift_L", "j" },
+			action = strata.window.swap("down"),
+		},
+		{
+			keys = { "Super_L", "Shift_L", "k" },
+			action = strata.window.swap("up"),
+		},
+		{
+			keys = { "Super_L", "Shift_L", "l" },
+			action = strata.window.swap("right"),
+		},
+		{
+			keys = { "Super_L", "Shift_L", "Return" },
+			action = strata.window.promote(),
+		},
+		{
+			keys = { "Super_L", "Control_L", "h" },
+			action = strata.window.shrink("right"),
+		},
+		{
+			keys = { "Super_L", "Control_L", "l" },
+			action = strata.window.grow("right"),
+		},
+		{
+			keys = { "Super_L", "Control_L", "k" },
+			action = strata.window.shrink("down"),
+		},
+		{
+			keys = { "Super_L", "Control_L", "j" },
+			action = strata.window.grow("down"),
+		},
+		{
+			keys = { "Super_L", "equal" },
+			action = strata.workspace.reset_ratios(),
+		},
+		{
+			keys = { "Super_L", "e" },
+			action = strata.window.toggle_split(),
+		},
+		{
+			keys = { "Super_L", "Alt_L", "h" },
+			action = strata.window.preselect("left"),
+		},
+		{
+			keys = { "Super_L", "Alt_L", "j" },
+			action = strata.window.preselect("down"),
+		},
+		{
+			keys = { "Super_L", "Alt_L", "k" },
+			action = strata.window.preselect("up"),
+		},
+		{
+			keys = { "Super_L", "Alt_L", "l" },
+			action = strata.window.preselect("right"),
+		},
+		{
+			keys = { "Super_L", "t" },
+			action = strata.workspace.set_layout("dwindle"),
+		},
+		{
+			keys = { "Super_L", "Shift_L", "t" },
+			action = strata.workspace.set_layout("master_stack"),
+		},
+		{
+			keys = { "Super_L", "w" },
+			action = strata.workspace.set_layout("monocle"),
+		},
+		{
+			keys = { "Super_L", "s" },
+			action = strata.workspace.set_layout("scrolling"),
+		},
+		{
+			keys = { "Super_L", "r" },
+			action = strata.window.cycle_width(),
+		},
+		{
+			keys = { "Super_L", "period" },
+			action = strata.output.focus("right"),
+		},
+		{
+			keys = { "Super_L", "comma" },
+			action = strata.output.focus("left"),
+		},
+		{
+			keys = { "Super_L", "Shift_L", "period" },
+			action = strata.window.move_to_output("right"),
+		},
+		{
+			keys = { "Super_L", "Shift_L", "comma" },
+			action = strata.window.move_to_output("left"),
+		},
+		{
+			keys = { "Super_L", "i" },
+			action = strata.workspace.inc_master_count(1),
+		},
+		{
+			keys = { "Super_L", "d" },
+			action = strata.workspace.inc_master_count(-1),
+		},
+		{
+			keys = { "Super_L", "bracketright" },
+			action = strata.workspace.inc_master_ratio(0.05),
+		},
+		{
+			keys = { "Super_L", "bracketleft" },
+			action = strata.workspace.inc_master_ratio(-0.05),
+		},
+		{
+			keys = { "Super_L", "Tab" },
+			action = strata.window.focus_next(),
+		},
+		{
+			keys = { "Super_L", "Shift_L", "Tab" },
+			action = strata.window.focus_prev(),
+		},
 		{
 			keys = { "Super_L", "b" },
 			action = function() -- Toggle border